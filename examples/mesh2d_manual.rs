@@ -13,7 +13,15 @@ use bevy::{
 
 use std::f32::consts::PI;
 
-use bevy_wireframe::wireframe2d::*;
+use bevy_wireframe::{
+    material2d::{WireframeMaterial2d, WireframeMaterial2dPlugin, WireframeMaterialMesh2dBundle},
+    mesh::{with_barycentric, ATTRIBUTE_WIRE_COLOR},
+    style::{
+        DashPattern, WireframeEdges, WireframeMode, WireframeSettings, WireframeSpace,
+        WireframeStyle,
+    },
+    wireframe2d::*,
+};
 
 fn main() {
     App::new()
@@ -25,15 +33,28 @@ fn main() {
             }),
             ..Default::default()
         }))
-        .add_plugins((WireframeMesh2dPlugin,))
+        .add_plugins((WireframeMesh2dPlugin, WireframeMaterial2dPlugin))
         .add_systems(Startup, star)
+        .add_systems(Update, toggle_global_wireframe)
         .run();
 }
 
+/// Press space to draw every mesh as a wireframe.
+fn toggle_global_wireframe(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<WireframeSettings>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        settings.global = !settings.global;
+    }
+}
+
 fn star(
     mut commands: Commands,
     // We will add a new Mesh for the star being created
     mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<WireframeMaterial2d>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
 ) {
     // Let's define the mesh for the object we want to draw: a nice star.
     // We will specify here what kind of topology is used to define the mesh,
@@ -44,9 +65,7 @@ fn star(
     // order to save on memory once it has been uploaded to the GPU.
     let mut star = Mesh::new(
         PrimitiveTopology::TriangleList,
-        // FIXME: Main world is required in order to allow PosBuffer to process
-        // the mesh too.
-        RenderAssetUsages::RENDER_WORLD, // | RenderAssetUsages::MAIN_WORLD,
+        RenderAssetUsages::RENDER_WORLD,
     );
 
     // Vertices need to have a position attribute. We will use the following
@@ -71,8 +90,24 @@ fn star(
         v_pos.push([r * a.sin(), r * a.cos(), 0.0]);
     }
 
+    // Give each vertex its own wire color: white at the center and a hue
+    // around the tips, blended along the edges between them.
+    let v_wire_color: Vec<[f32; 4]> = (0..v_pos.len())
+        .map(|i| {
+            let color = if i == 0 {
+                Color::WHITE
+            } else {
+                Color::hsl(i as f32 * 36.0, 1.0, 0.5)
+            }
+            .to_linear();
+            [color.red, color.green, color.blue, color.alpha]
+        })
+        .collect();
+
     // Set the position attribute
     star.insert_attribute(Mesh::ATTRIBUTE_POSITION, v_pos);
+    // Set the wire color attribute, which replaces the style's wire color
+    star.insert_attribute(ATTRIBUTE_WIRE_COLOR, v_wire_color);
 
     // Now, we specify the indices of the vertex that are going to compose the
     // triangles in our star. Vertices in triangles have to be specified in CCW
@@ -88,28 +123,82 @@ fn star(
         indices.extend_from_slice(&[0, i, i - 1]);
     }
     star.insert_indices(Indices::U32(indices));
-    star.duplicate_vertices();
 
     // The `Handle<Mesh>` needs to be wrapped in a `Mesh2dHandle` to use 2d
     // rendering instead of 3d.
     let handle = Mesh2dHandle(meshes.add(star));
     commands.spawn((
         WireframeMesh2d,
+        WireframeStyle {
+            wire_color: Color::srgb(0.0, 0.0, 1.0),
+            fill_color: Color::srgb(1.0, 1.0, 0.0),
+            dash: Some(DashPattern::dashed(10.0, WireframeSpace::Model)),
+            ..default()
+        },
         handle.clone(),
         SpatialBundle::INHERITED_IDENTITY,
     ));
     let shape = Circle { radius: 50.0 };
-    let mut circle: Mesh = shape.into();
-    circle.duplicate_vertices();
-    // circle.asset_usage = RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD;
-    let handle = Mesh2dHandle(meshes.add(circle.clone()));
+    let circle: Mesh = shape.into();
+    let handle = Mesh2dHandle(meshes.add(circle));
 
     commands.spawn((
         WireframeMesh2d,
+        // Outline the circle without its fan spokes, 2 pixels wide however it
+        // is scaled.
+        WireframeStyle {
+            line_width: 2.0,
+            space: WireframeSpace::Screen,
+            mode: WireframeMode::Outline,
+            ..default()
+        },
         handle,
         SpatialBundle::from_transform(Transform::from_xyz(-300.0, -100.0, 2.0)),
     ));
 
+    // A rectangle drawn as a quad rather than two triangles.
+    commands.spawn((
+        WireframeMesh2d,
+        WireframeStyle {
+            line_width: 2.0,
+            space: WireframeSpace::Screen,
+            edges: WireframeEdges::Polygons,
+            ..default()
+        },
+        Mesh2dHandle(meshes.add(Rectangle::new(100.0, 60.0))),
+        SpatialBundle::from_transform(Transform::from_xyz(300.0, -100.0, 2.0)),
+    ));
+
+    // A hexagon drawn with a material, which batches like any other 2d
+    // material but needs barycentric coordinates.
+    commands.spawn(WireframeMaterialMesh2dBundle {
+        mesh: Mesh2dHandle(meshes.add(with_barycentric(RegularPolygon::new(50.0, 6).into()))),
+        material: materials.add(WireframeMaterial2d {
+            wire_color: Color::srgb(0.0, 0.5, 0.0),
+            line_width: 1.5,
+            space: WireframeSpace::Screen,
+            ..default()
+        }),
+        transform: Transform::from_xyz(300.0, 100.0, 2.0),
+        ..default()
+    });
+
+    // An ordinary colored mesh with wires drawn over it.
+    commands.spawn((
+        ColorMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(RegularPolygon::new(50.0, 5))),
+            material: color_materials.add(Color::srgb(0.3, 0.3, 0.8)),
+            transform: Transform::from_xyz(-300.0, 100.0, 2.0),
+            ..default()
+        },
+        WireframeMesh2d,
+        WireframeStyle {
+            line_width: 1.5,
+            space: WireframeSpace::Screen,
+            ..WireframeStyle::overlay(Color::WHITE)
+        },
+    ));
+
     // Spawn the camera
     commands.spawn(Camera2dBundle::default());
 }
@@ -0,0 +1,93 @@
+//! This example shows a rotating cube drawn with [`WireframeMesh3d`], and a
+//! lit torus with a wireframe overlay.
+
+use bevy::{pbr::ExtendedMaterial, prelude::*};
+
+use bevy_wireframe::{
+    material3d::{WireframeExtension, WireframeExtensionPlugin, WireframeStandardMaterial},
+    mesh::with_barycentric,
+    style::{WireframeMode, WireframeStyle},
+    wireframe3d::*,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: [800., 400.].into(),
+                title: "Wireframe 3d".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }))
+        .add_plugins((WireframeMesh3dPlugin, WireframeExtensionPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, rotate)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotate;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<WireframeStandardMaterial>>,
+) {
+    let cube: Mesh = Cuboid::new(1.0, 1.0, 1.0).into();
+
+    commands.spawn((
+        WireframeMesh3d,
+        WireframeStyle {
+            line_width: 0.02,
+            ..default()
+        },
+        meshes.add(cube),
+        SpatialBundle::from_transform(Transform::from_xyz(-0.8, 0.0, 0.0)),
+        Rotate,
+    ));
+
+    // A hidden line drawing only shows the edges facing the camera.
+    commands.spawn((
+        WireframeMesh3d,
+        WireframeStyle {
+            line_width: 0.02,
+            fill_color: Color::NONE,
+            mode: WireframeMode::HiddenLine,
+            ..default()
+        },
+        meshes.add(Sphere::new(0.5).mesh().uv(16, 8)),
+        SpatialBundle::from_transform(Transform::from_xyz(0.8, 0.0, 0.0)),
+        Rotate,
+    ));
+
+    // A lit model with a wireframe overlay, as in a modeling tool.
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(with_barycentric(Torus::new(0.2, 0.4).into())),
+            material: materials.add(ExtendedMaterial {
+                base: Color::srgb(0.8, 0.7, 0.6).into(),
+                extension: WireframeExtension::default(),
+            }),
+            transform: Transform::from_xyz(0.0, -0.8, 0.0),
+            ..default()
+        },
+        Rotate,
+    ));
+
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 1.5, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn rotate(mut query: Query<&mut Transform, With<Rotate>>, time: Res<Time>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() * 0.5);
+    }
+}
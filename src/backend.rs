@@ -0,0 +1,247 @@
+use crate::{
+    compute::{WireframeMeshes, WORKGROUP_SIZE},
+    mesh::{insert_face_attributes, line_quads, triangle_list},
+    style::WireframeStyleUniform,
+};
+use bevy::{
+    app::App,
+    asset::{AssetEvent, AssetId, Assets, Handle},
+    ecs::{
+        event::EventReader,
+        system::{ResMut, Resource},
+    },
+    log::warn,
+    pbr::MeshUniform,
+    render::{
+        mesh::{Mesh, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+        render_resource::{GpuArrayBuffer, PrimitiveTopology, ShaderSize},
+        renderer::RenderDevice,
+        RenderApp,
+    },
+    sprite::Mesh2dUniform,
+    utils::{HashMap, HashSet},
+};
+
+/// The storage buffers the wireframe shader reads in its vertex stage
+const WIREFRAME_STORAGE_BUFFERS: u32 = 9;
+
+/// How the face data of wireframe meshes is made and read
+///
+/// It is chosen from the [`RenderDevice`]'s limits when the wireframe plugins
+/// are finished, unless the app already has one.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WireframeBackend {
+    /// Face data is measured by compute shaders and read from storage buffers
+    /// by the wireframe shader.
+    Compute,
+    /// Face data is measured on the CPU when a mesh is first drawn as a
+    /// wireframe or modified, and baked into vertex attributes, for WebGL2 and
+    /// other targets without compute shaders or vertex stage storage buffers.
+    /// A [`RENDER_WORLD`](RenderAssetUsages::RENDER_WORLD) only mesh leaves
+    /// the main world once it is extracted, so it is baked as soon as it is
+    /// added, in case a wireframe draws it later.
+    ///
+    /// Triangle meshes are drawn from an unindexed copy with an
+    /// [`ATTRIBUTE_BARYCENTRIC`](crate::mesh::ATTRIBUTE_BARYCENTRIC),
+    /// [`ATTRIBUTE_FACE`](crate::mesh::ATTRIBUTE_FACE),
+    /// [`ATTRIBUTE_CREASE_ANGLES`](crate::mesh::ATTRIBUTE_CREASE_ANGLES) and
+    /// [`ATTRIBUTE_EDGE_MASK`](crate::mesh::ATTRIBUTE_EDGE_MASK). Without the
+    /// neighbors of each triangle, [`WireframeMode::Outline`] only draws the
+    /// boundary edges, and the [`ATTRIBUTE_EDGE_COLOR`] of a mesh and the pose
+    /// of skinned and morphed meshes are ignored. Triangle strips are copied
+    /// as triangle lists, keeping only the attributes bevy and this crate
+    /// define.
+    ///
+    /// Line meshes are drawn from a copy with a quad for each segment and an
+    /// [`ATTRIBUTE_SEGMENT_END`](crate::mesh::ATTRIBUTE_SEGMENT_END).
+    ///
+    /// [`WireframeMode::Outline`]: crate::style::WireframeMode::Outline
+    /// [`ATTRIBUTE_EDGE_COLOR`]: crate::mesh::ATTRIBUTE_EDGE_COLOR
+    VertexAttributes,
+}
+
+impl WireframeBackend {
+    /// The backend a render device supports
+    pub fn from_device(render_device: &RenderDevice) -> Self {
+        let limits = render_device.limits();
+        // WebGL2 has neither compute workgroups nor storage buffers.
+        if limits.max_compute_workgroups_per_dimension == 0
+            || limits.max_compute_invocations_per_workgroup < WORKGROUP_SIZE
+            || limits.max_storage_buffers_per_shader_stage < WIREFRAME_STORAGE_BUFFERS
+        {
+            WireframeBackend::VertexAttributes
+        } else {
+            WireframeBackend::Compute
+        }
+    }
+
+    /// The number of wireframe styles bound at once with
+    /// [`WireframeBackend::VertexAttributes`], as many as the 2d or 3d mesh
+    /// uniforms bound at the same dynamic offset
+    pub(crate) fn style_batch_size(render_device: &RenderDevice) -> u32 {
+        let mesh2d = GpuArrayBuffer::<Mesh2dUniform>::batch_size(render_device);
+        let mesh3d = GpuArrayBuffer::<MeshUniform>::batch_size(render_device);
+        match mesh2d.max(mesh3d) {
+            Some(batch_size) => batch_size,
+            // The mesh uniforms are in storage, so there is a single batch.
+            None => {
+                render_device
+                    .limits()
+                    .max_uniform_buffer_binding_size
+                    .min(1 << 16)
+                    / WireframeStyleUniform::SHADER_SIZE.get() as u32
+            }
+        }
+    }
+}
+
+/// The app's [`WireframeBackend`], inserted into the main and render worlds
+/// by the first plugin to ask for it
+pub(crate) fn init_backend(app: &mut App) -> WireframeBackend {
+    let backend = match app.world().get_resource::<WireframeBackend>() {
+        Some(backend) => *backend,
+        None => WireframeBackend::from_device(app.world().resource::<RenderDevice>()),
+    };
+    app.insert_resource(backend);
+    app.sub_app_mut(RenderApp).insert_resource(backend);
+    backend
+}
+
+/// The copies of the wireframe meshes that their face data is baked into with
+/// [`WireframeBackend::VertexAttributes`], by the id of each mesh
+///
+/// The copies are render world only meshes, so the app's own meshes are never
+/// modified. Point meshes have neither triangles nor segments, and are left as
+/// they are.
+#[derive(Resource, Default)]
+pub(crate) struct BakedMeshes {
+    /// The copy of each baked mesh, or `None` if it is drawn as it is
+    meshes: HashMap<AssetId<Mesh>, Option<Handle<Mesh>>>,
+    /// The ids of the copies, which are render world only meshes themselves
+    /// but never baked
+    copies: HashSet<AssetId<Mesh>>,
+}
+
+impl BakedMeshes {
+    /// The mesh a wireframe of mesh `id` is drawn from with `backend`, once
+    /// its face data is baked
+    pub(crate) fn face_mesh(
+        &self,
+        backend: WireframeBackend,
+        id: impl Into<AssetId<Mesh>>,
+    ) -> Option<AssetId<Mesh>> {
+        let id = id.into();
+        match backend {
+            WireframeBackend::Compute => Some(id),
+            WireframeBackend::VertexAttributes => {
+                let baked = self.meshes.get(&id)?;
+                Some(baked.as_ref().map_or(id, Handle::id))
+            }
+        }
+    }
+}
+
+/// How the wireframe of a mesh is drawn with
+/// [`WireframeBackend::VertexAttributes`]
+enum BakedMesh {
+    /// From a copy of the mesh with its face data baked in
+    Copy(Mesh),
+    /// From the mesh itself, which has no triangles or segments to bake
+    Unchanged,
+}
+
+/// Bake the face data of a triangle mesh, or the segment quads of a line mesh,
+/// into a copy of it
+fn bake_mesh(id: AssetId<Mesh>, mesh: &Mesh) -> Option<BakedMesh> {
+    if !matches!(
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+        Some(VertexAttributeValues::Float32x3(_))
+    ) {
+        warn!("Skipping wireframe of mesh {id:?} without Float32x3 positions");
+        return None;
+    }
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => {
+            let mut baked = mesh.clone();
+            baked.asset_usage = RenderAssetUsages::RENDER_WORLD;
+            insert_face_attributes(&mut baked);
+            Some(BakedMesh::Copy(baked))
+        }
+        PrimitiveTopology::TriangleStrip => {
+            let mut baked = triangle_list(mesh);
+            baked.asset_usage = RenderAssetUsages::RENDER_WORLD;
+            insert_face_attributes(&mut baked);
+            Some(BakedMesh::Copy(baked))
+        }
+        PrimitiveTopology::LineList | PrimitiveTopology::LineStrip => {
+            line_quads(mesh).map(BakedMesh::Copy)
+        }
+        _ => Some(BakedMesh::Unchanged),
+    }
+}
+
+/// Bake the face data of each mesh in [`WireframeMeshes`] into a copy of it
+/// for [`WireframeBackend::VertexAttributes`] while it is still in the main
+/// world, and again when it is modified
+pub(crate) fn bake_face_attributes(
+    mut events: EventReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut wireframe_meshes: ResMut<WireframeMeshes>,
+    mut baked: ResMut<BakedMeshes>,
+) {
+    let mut modified = Vec::new();
+    for event in events.read() {
+        match event {
+            // The copies are added and modified too, but only the meshes
+            // they are baked from are built.
+            AssetEvent::Added { id } if !baked.copies.contains(id) => {
+                wireframe_meshes.insert_render_world_mesh(*id, &meshes);
+            }
+            AssetEvent::Modified { id } if wireframe_meshes.is_built(*id) => {
+                modified.push(*id);
+            }
+            AssetEvent::Unused { id } => {
+                wireframe_meshes.forget(*id);
+                // Drops the copy along with it.
+                if let Some(Some(copy)) = baked.meshes.remove(id) {
+                    baked.copies.remove(&copy.id());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut bakes = Vec::new();
+    for id in modified {
+        if let Some(mesh) = meshes.get(id) {
+            bakes.push((id, bake_mesh(id, mesh)));
+        }
+    }
+    for (id, mesh) in wireframe_meshes.take_new(&meshes) {
+        bakes.push((id, bake_mesh(id, mesh)));
+    }
+    for (id, mesh) in bakes {
+        match (mesh, baked.meshes.get(&id)) {
+            (Some(BakedMesh::Copy(mesh)), Some(Some(copy))) => {
+                let copy = copy.id();
+                meshes.insert(copy, mesh);
+            }
+            (Some(BakedMesh::Copy(mesh)), _) => {
+                let copy = meshes.add(mesh);
+                baked.copies.insert(copy.id());
+                baked.meshes.insert(id, Some(copy));
+            }
+            (Some(BakedMesh::Unchanged), _) => {
+                if let Some(Some(copy)) = baked.meshes.insert(id, None) {
+                    baked.copies.remove(&copy.id());
+                }
+            }
+            (None, _) => {
+                if let Some(Some(copy)) = baked.meshes.remove(&id) {
+                    baked.copies.remove(&copy.id());
+                }
+            }
+        }
+    }
+}
@@ -1,156 +1,1036 @@
+use crate::backend::{bake_face_attributes, init_backend, BakedMeshes, WireframeBackend};
+use crate::mesh::{
+    edge_mask, edge_mask_attribute, line_list_indices, line_segments, triangle_creases,
+    triangle_edge_colors, triangle_list_indices, triangles, Adjacency, ATTRIBUTE_EDGE_COLOR,
+    ATTRIBUTE_EDGE_MASK, ATTRIBUTE_WIRE_COLOR,
+};
 use bevy::{
-    app::{App, Plugin},
-    asset::{embedded_asset, DirectAssetAccessExt},
+    app::{App, Last, Plugin},
+    asset::{embedded_asset, AssetEvent, AssetEvents, AssetId, Assets, DirectAssetAccessExt},
     ecs::{
         component::Component,
-        entity::Entity,
-        query::{QueryState, With},
-        schedule::IntoSystemConfigs,
-        system::{lifetimeless::SRes, Commands, Query, Res, ResMut, Resource, SystemParamItem},
+        event::EventReader,
+        schedule::{IntoSystemConfigs, IntoSystemSetConfigs, SystemSet},
+        system::{Res, ResMut, Resource},
         world::{FromWorld, World},
     },
     log::warn,
-    math::Vec4,
+    math::{UVec4, Vec4},
     prelude::{Deref, DerefMut},
     render::{
-        mesh::{GpuMesh, Mesh, VertexAttributeValues},
-        render_asset::{PrepareAssetError, RenderAssetUsages, RenderAssets},
-        render_asset::{RenderAsset, RenderAssetPlugin},
-        render_graph::{
-            Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel, SlotInfo, SlotType,
-        },
+        mesh::{GpuMesh, Mesh, MeshVertexAttribute, MeshVertexAttributeId, VertexAttributeValues},
+        render_asset::{prepare_assets, RenderAssetUsages, RenderAssets},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel},
         render_resource::{
-            binding_types::{storage_buffer, storage_buffer_read_only},
+            binding_types::{storage_buffer, storage_buffer_read_only, uniform_buffer},
             BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
-            BufferDescriptor, BufferInitDescriptor, BufferUsages, CachedComputePipelineId,
-            ComputePassDescriptor, ComputePipelineDescriptor, PipelineCache, Shader, ShaderStages,
+            BufferDescriptor, BufferId, BufferInitDescriptor, BufferUsages,
+            CachedComputePipelineId, ComputePassDescriptor, ComputePipelineDescriptor,
+            PipelineCache, PrimitiveTopology, Shader, ShaderDefVal, ShaderStages, TextureView,
+            VertexFormat,
         },
-        renderer::{RenderContext, RenderDevice},
-        texture::GpuImage,
-        Render, RenderApp, RenderSet,
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        ExtractSchedule, MainWorld, Render, RenderApp, RenderSet,
     },
-    sprite::{Mesh2dHandle, RenderMesh2dInstance},
+    utils::{HashMap, HashSet},
+};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
 };
-
-use crate::wireframe2d::{WireframeMesh2d, WireframeMesh2dInstances};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
 pub struct FaceLabel;
 
-#[derive(Component)]
+/// The number of triangles each face compute workgroup handles
+pub(crate) const WORKGROUP_SIZE: u32 = 64;
+
+/// The workgroups to dispatch for a mesh's triangles or vertices, split into
+/// rows when there are more than fit in one dimension
+pub(crate) fn dispatch_size(count: u32, max_per_dimension: u32) -> (u32, u32) {
+    let workgroups = count.div_ceil(WORKGROUP_SIZE);
+    let x = workgroups.min(max_per_dimension);
+    (x, workgroups.div_ceil(x.max(1)))
+}
+
+/// The mesh whose per-triangle face data an entity needs in the render world
+#[derive(Component, Clone, Copy, Deref, DerefMut)]
+pub struct FaceMesh(pub AssetId<Mesh>);
+
+/// A face compute dispatch for a range of one mesh's triangles
+#[derive(Clone)]
 pub struct FaceBinding {
     bind_group: BindGroup,
-    vertex_count: usize,
-    dist_buffer: Buffer,
+    /// The number of triangles in the range
+    triangle_count: usize,
+    indexed: bool,
+}
+
+impl FaceBinding {
+    /// Compute the face data of `triangles` of `pos_buffer`'s mesh from
+    /// `positions`, laid out as described by `vertex_layout`, into
+    /// `face_buffer`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        render_device: &RenderDevice,
+        pipeline: &FacePipeline,
+        pos_buffer: &PosBuffer,
+        positions: &Buffer,
+        vertex_layout: &Buffer,
+        face_buffer: &Buffer,
+        triangles: Range<usize>,
+    ) -> Self {
+        let triangle_range = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("triangle_range_buffer"),
+            contents: bytemuck::cast_slice(&[UVec4::new(
+                triangles.start as u32,
+                triangles.len() as u32,
+                0,
+                0,
+            )]),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = render_device.create_bind_group(
+            None,
+            &pipeline.layout,
+            &BindGroupEntries::sequential((
+                positions.as_entire_buffer_binding(),
+                face_buffer.as_entire_buffer_binding(),
+                pos_buffer.index_binding().as_entire_buffer_binding(),
+                vertex_layout.as_entire_buffer_binding(),
+                triangle_range.as_entire_buffer_binding(),
+            )),
+        );
+        FaceBinding {
+            bind_group,
+            triangle_count: triangles.len(),
+            indexed: pos_buffer.index_buffer.is_some(),
+        }
+    }
+}
+
+/// A dispatch that deforms the positions of one skinned or morphed entity
+/// before its face compute
+pub struct DeformBinding {
+    pub(crate) bind_group: BindGroup,
+    pub(crate) pipeline: CachedComputePipelineId,
+    pub(crate) vertex_count: usize,
+}
+
+/// The meshes whose face data has been computed, and those to compute this
+/// frame
+///
+/// Face data is computed once per [`PosBuffer`], when its mesh is uploaded,
+/// and shared by every entity using the mesh.
+#[derive(Resource, Default)]
+pub struct FaceComputes {
+    /// The face buffer each mesh's face data was computed into
+    computed: HashMap<AssetId<Mesh>, BufferId>,
+    pub(crate) pending: Vec<FaceBinding>,
+    /// Dispatched before the face computes, whose deformed positions they
+    /// write
+    pub(crate) deforms: Vec<DeformBinding>,
 }
 
 #[derive(Resource)]
 pub struct FacePipeline {
-    layout: BindGroupLayout,
-    pipeline: CachedComputePipelineId,
+    pub(crate) layout: BindGroupLayout,
+    pub(crate) pipeline: CachedComputePipelineId,
+    /// Reads the vertices of each triangle through the mesh's indices
+    pub(crate) indexed_pipeline: CachedComputePipelineId,
+}
+
+/// The per-triangle data of a mesh that is built on the CPU
+///
+/// It is built in the main world as soon as the mesh is added or modified,
+/// before a [`RENDER_WORLD`](bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD)
+/// only mesh is extracted and removed from its [`Assets`].
+pub struct FaceData {
+    /// The mesh's vertex buffer, uploaded in place of the one bevy makes so
+    /// that it can also be bound as storage. The face data is dropped once it
+    /// is uploaded.
+    vertex_data: Vec<u8>,
+    vertex_count: usize,
+    indices: Option<Vec<u32>>,
+    triangle_count: usize,
+    segment_count: usize,
+    masks: Vec<u32>,
+    creases: Vec<[f32; 4]>,
+    neighbors: Vec<[u32; 4]>,
+    wire_colors: Option<Vec<[f32; 4]>>,
+    edge_colors: Option<Vec<[f32; 4]>>,
+    /// The mesh's [`Mesh::ATTRIBUTE_JOINT_INDEX`] and
+    /// [`Mesh::ATTRIBUTE_JOINT_WEIGHT`], if it is skinned
+    joints: Option<(Vec<[u32; 4]>, Vec<[f32; 4]>)>,
+}
+
+impl FaceData {
+    /// The face data of mesh `id` and the shape it was built from, or `None` if
+    /// it has no `Float32x3` positions
+    fn with_shape(id: AssetId<Mesh>, mesh: &Mesh) -> Option<(Self, MeshShape)> {
+        let (shape, positions) = MeshShape::new(mesh)?;
+        let triangle_count = shape.triangles.len();
+        let mut masks = shape.masks(positions, 0..triangle_count);
+        let mut creases = shape.creases(positions, 0..triangle_count);
+        let mut neighbors = shape.neighbors(0..triangle_count);
+        if triangle_count == 0 {
+            // Line meshes have no triangles, but the buffers must not be empty.
+            masks.push(0);
+            creases.push([0.0; 4]);
+            neighbors.push([0; 4]);
+        }
+        let face_data = FaceData {
+            vertex_data: mesh.get_vertex_buffer_data(),
+            vertex_count: positions.len(),
+            indices: triangle_list_indices(mesh).or_else(|| line_list_indices(mesh)),
+            triangle_count,
+            segment_count: line_segments(mesh).len(),
+            masks,
+            creases,
+            neighbors,
+            wire_colors: shape.wire_colors.clone(),
+            edge_colors: shape
+                .edge_colors(0..triangle_count)
+                .filter(|colors| !colors.is_empty()),
+            joints: joints(id, mesh),
+        };
+        Some((face_data, shape))
+    }
+}
+
+/// The joint indices and weights of a skinned mesh, converted from any of the
+/// integer and normalized formats glTF allows
+fn joints(id: AssetId<Mesh>, mesh: &Mesh) -> Option<(Vec<[u32; 4]>, Vec<[f32; 4]>)> {
+    let indices = mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX)?;
+    let weights = mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)?;
+    let converted_indices: Option<Vec<[u32; 4]>> = match indices {
+        VertexAttributeValues::Uint8x4(indices) => {
+            Some(indices.iter().map(|index| index.map(u32::from)).collect())
+        }
+        VertexAttributeValues::Uint16x4(indices) => {
+            Some(indices.iter().map(|index| index.map(u32::from)).collect())
+        }
+        VertexAttributeValues::Uint32x4(indices) => Some(indices.clone()),
+        _ => None,
+    };
+    let converted_weights: Option<Vec<[f32; 4]>> = match weights {
+        VertexAttributeValues::Float32x4(weights) => Some(weights.clone()),
+        VertexAttributeValues::Unorm8x4(weights) => Some(
+            weights
+                .iter()
+                .map(|weight| weight.map(|w| w as f32 / u8::MAX as f32))
+                .collect(),
+        ),
+        VertexAttributeValues::Unorm16x4(weights) => Some(
+            weights
+                .iter()
+                .map(|weight| weight.map(|w| w as f32 / u16::MAX as f32))
+                .collect(),
+        ),
+        _ => None,
+    };
+    let joints = converted_indices.zip(converted_weights);
+    if joints.is_none() {
+        warn!(
+            "Drawing wireframe of mesh {id:?} in its bind pose, as its {:?} joint indices \
+            and {:?} joint weights aren't supported",
+            VertexFormat::from(indices),
+            VertexFormat::from(weights),
+        );
+    }
+    joints
+}
+
+/// The values of a `Float32x4` attribute of a mesh, if it has them
+fn float32x4(mesh: &Mesh, attribute: MeshVertexAttribute) -> Option<&[[f32; 4]]> {
+    match mesh.attribute(attribute) {
+        Some(VertexAttributeValues::Float32x4(values)) => Some(values),
+        _ => None,
+    }
+}
+
+/// Everything about a mesh that its face buffers are laid out by
+///
+/// Indices are compared by a hash of their values, so a mesh whose indices
+/// are rewritten is built again even if their count stays the same.
+#[derive(PartialEq)]
+struct MeshLayout {
+    topology: PrimitiveTopology,
+    vertex_count: usize,
+    indices: Option<u64>,
+    attributes: Vec<(MeshVertexAttributeId, VertexFormat)>,
+}
+
+impl MeshLayout {
+    fn new(mesh: &Mesh) -> Self {
+        MeshLayout {
+            topology: mesh.primitive_topology(),
+            vertex_count: mesh.count_vertices(),
+            indices: mesh.get_index_buffer_bytes().map(|bytes| {
+                let mut hasher = DefaultHasher::new();
+                bytes.hash(&mut hasher);
+                hasher.finish()
+            }),
+            attributes: mesh
+                .attributes()
+                .map(|(id, values)| (id, VertexFormat::from(values)))
+                .collect(),
+        }
+    }
+}
+
+/// What a mesh's face data was last built from, kept for the meshes in the
+/// main world, which can be modified
+struct MeshShape {
+    layout: MeshLayout,
+    /// The mesh's [`ATTRIBUTE_WIRE_COLOR`], if it has one
+    wire_colors: Option<Vec<[f32; 4]>>,
+    /// The mesh's [`ATTRIBUTE_EDGE_COLOR`], if it has one
+    edge_colors: Option<Vec<[f32; 4]>>,
+    /// The mesh's [`ATTRIBUTE_EDGE_MASK`], if it has one that is read
+    edge_masks: Option<Vec<u32>>,
+    triangles: Vec<[u32; 3]>,
+    /// Where the triangles of each vertex start in `vertex_triangles`, with
+    /// those of vertex `v` ending where those of vertex `v + 1` start
+    triangle_offsets: Vec<u32>,
+    vertex_triangles: Vec<u32>,
+    adjacency: Adjacency,
 }
 
+impl MeshShape {
+    /// The shape of a mesh and its positions, or `None` if it has no
+    /// `Float32x3` positions
+    fn new(mesh: &Mesh) -> Option<(Self, &[[f32; 3]])> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let triangles = triangles(mesh);
+        let mut triangle_offsets = vec![0; positions.len() + 1];
+        for triangle in &triangles {
+            for v in triangle {
+                triangle_offsets[*v as usize + 1] += 1;
+            }
+        }
+        for v in 0..positions.len() {
+            triangle_offsets[v + 1] += triangle_offsets[v];
+        }
+        let mut next = triangle_offsets.clone();
+        let mut vertex_triangles = vec![0; 3 * triangles.len()];
+        for (t, triangle) in triangles.iter().enumerate() {
+            for v in triangle {
+                vertex_triangles[next[*v as usize] as usize] = t as u32;
+                next[*v as usize] += 1;
+            }
+        }
+        let shape = MeshShape {
+            layout: MeshLayout::new(mesh),
+            wire_colors: float32x4(mesh, ATTRIBUTE_WIRE_COLOR).map(<[_]>::to_vec),
+            edge_colors: float32x4(mesh, ATTRIBUTE_EDGE_COLOR).map(<[_]>::to_vec),
+            edge_masks: edge_mask_attribute(mesh).map(<[_]>::to_vec),
+            adjacency: Adjacency::new(positions, &triangles),
+            triangles,
+            triangle_offsets,
+            vertex_triangles,
+        };
+        Some((shape, positions))
+    }
+
+    /// The triangles that use vertex `v`
+    fn vertex_triangles(&self, v: usize) -> &[u32] {
+        let offsets = self.triangle_offsets[v] as usize..self.triangle_offsets[v + 1] as usize;
+        &self.vertex_triangles[offsets]
+    }
+
+    fn masks(&self, positions: &[[f32; 3]], triangles: Range<usize>) -> Vec<u32> {
+        let masks = self.edge_masks.as_deref();
+        triangles
+            .map(|t| edge_mask(masks, positions, &self.triangles, &self.adjacency, t))
+            .collect()
+    }
+
+    fn creases(&self, positions: &[[f32; 3]], triangles: Range<usize>) -> Vec<[f32; 4]> {
+        triangles
+            .map(|t| triangle_creases(positions, &self.triangles, &self.adjacency, t))
+            .collect()
+    }
+
+    fn neighbors(&self, triangles: Range<usize>) -> Vec<[u32; 4]> {
+        triangles
+            .map(|t| self.adjacency.neighbor_index(t))
+            .collect()
+    }
+
+    /// The colors of each edge of the triangles, three per triangle
+    fn edge_colors(&self, triangles: Range<usize>) -> Option<Vec<[f32; 4]>> {
+        let colors = self.edge_colors.as_ref()?;
+        Some(
+            triangles
+                .flat_map(|t| triangle_edge_colors(colors, &self.triangles, &self.adjacency, t))
+                .collect(),
+        )
+    }
+}
+
+/// The shapes of the wireframe meshes in the main world
+#[derive(Resource, Default)]
+struct MeshShapes(HashMap<AssetId<Mesh>, MeshShape>);
+
+/// The meshes drawn as wireframes, and the meshes that only live in the render
+/// world, the only ones whose face data is built
+///
+/// The wireframe plugins add the meshes of their entities each frame in
+/// [`CollectWireframeMeshes`]. A
+/// [`RENDER_WORLD`](bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD)
+/// only mesh leaves the main world once it is extracted, so it is added as
+/// soon as it is, in case a wireframe draws it later.
+#[derive(Resource, Default)]
+pub(crate) struct WireframeMeshes {
+    /// The meshes of the wireframe entities this frame
+    used: HashSet<AssetId<Mesh>>,
+    /// The meshes whose face data has been built, or failed to build
+    built: HashSet<AssetId<Mesh>>,
+}
+
+impl WireframeMeshes {
+    /// Mark a mesh as drawn as a wireframe this frame
+    pub(crate) fn insert(&mut self, id: impl Into<AssetId<Mesh>>) {
+        self.used.insert(id.into());
+    }
+
+    /// Mark a mesh added to `meshes` as drawn as a wireframe if it only lives
+    /// in the render world, and build it again if it was added back after it
+    /// was extracted
+    pub(crate) fn insert_render_world_mesh(&mut self, id: AssetId<Mesh>, meshes: &Assets<Mesh>) {
+        if meshes
+            .get(id)
+            .is_some_and(|mesh| !mesh.asset_usage.contains(RenderAssetUsages::MAIN_WORLD))
+        {
+            self.built.remove(&id);
+            self.used.insert(id);
+        }
+    }
+
+    /// Whether a mesh's face data has been built
+    pub(crate) fn is_built(&self, id: AssetId<Mesh>) -> bool {
+        self.built.contains(&id)
+    }
+
+    /// Forget a mesh that is no longer used
+    pub(crate) fn forget(&mut self, id: AssetId<Mesh>) {
+        self.built.remove(&id);
+    }
+
+    /// Take the meshes used for the first time this frame, which may have been
+    /// added long before, and mark them built
+    pub(crate) fn take_new<'a>(
+        &mut self,
+        meshes: &'a Assets<Mesh>,
+    ) -> Vec<(AssetId<Mesh>, &'a Mesh)> {
+        let mut new = Vec::new();
+        for id in self.used.drain() {
+            if self.built.contains(&id) {
+                continue;
+            }
+            let Some(mesh) = meshes.get(id) else {
+                // Meshes that are still loading are built once they are added.
+                continue;
+            };
+            self.built.insert(id);
+            new.push((id, mesh));
+        }
+        new
+    }
+}
+
+/// The systems that add to [`WireframeMeshes`], which run in [`Last`] before
+/// the face data is built
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CollectWireframeMeshes;
+
+/// The changes to the face data of a modified mesh whose layout is unchanged
+pub struct FaceUpdate {
+    /// The mesh's vertex buffer, written whole since bevy uploads the whole
+    /// mesh again too
+    vertex_data: Vec<u8>,
+    /// The vertices from the first to the last one that changed
+    vertices: Range<usize>,
+    /// The [`ATTRIBUTE_WIRE_COLOR`] of `vertices`
+    wire_colors: Option<Vec<[f32; 4]>>,
+    /// The triangles with a vertex that changed and their neighbors, before
+    /// and after, whose face data is computed again
+    triangles: Range<usize>,
+    masks: Vec<u32>,
+    creases: Vec<[f32; 4]>,
+    neighbors: Vec<[u32; 4]>,
+    /// The colors of each edge of `triangles`, three per triangle
+    edge_colors: Option<Vec<[f32; 4]>>,
+}
+
+/// The range of the values that differ between `new` and `old`, empty if none
+/// do
+fn changed<T: PartialEq>(new: &[T], old: &[T]) -> Range<usize> {
+    changed_where(new.len().min(old.len()), |i| new[i] != old[i])
+}
+
+/// The range from the first to the last of `count` values that `differs`,
+/// empty if none do
+fn changed_where(count: usize, differs: impl Fn(usize) -> bool) -> Range<usize> {
+    let first = (0..count).position(&differs);
+    let last = (0..count).rposition(&differs);
+    match first.zip(last) {
+        Some((first, last)) => first..last + 1,
+        None => 0..0,
+    }
+}
+
+/// The smallest range that covers both `a` and `b`
+fn union(a: Range<usize>, b: Range<usize>) -> Range<usize> {
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => b,
+        (_, true) => a,
+        _ => a.start.min(b.start)..a.end.max(b.end),
+    }
+}
+
+impl FaceUpdate {
+    /// The changes to a mesh since its `shape`, which is updated to match
+    ///
+    /// Only the triangles around the vertices that changed are measured
+    /// again, and welded to their new neighbors.
+    fn new(mesh: &Mesh, shape: &mut MeshShape) -> Option<Self> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let wire_colors = float32x4(mesh, ATTRIBUTE_WIRE_COLOR);
+        let edge_colors = float32x4(mesh, ATTRIBUTE_EDGE_COLOR);
+        let edge_masks = match mesh.attribute(ATTRIBUTE_EDGE_MASK) {
+            Some(VertexAttributeValues::Uint32(masks)) if shape.edge_masks.is_some() => {
+                Some(masks.as_slice())
+            }
+            _ => None,
+        };
+
+        let vertices = [
+            changed_where(positions.len(), |v| {
+                !shape.adjacency.is_welded_at(v, &positions[v])
+            }),
+            wire_colors
+                .zip(shape.wire_colors.as_deref())
+                .map_or(0..0, |(new, old)| changed(new, old)),
+            edge_colors
+                .zip(shape.edge_colors.as_deref())
+                .map_or(0..0, |(new, old)| changed(new, old)),
+            edge_masks
+                .zip(shape.edge_masks.as_deref())
+                .map_or(0..0, |(new, old)| changed(new, old)),
+        ]
+        .into_iter()
+        .fold(0..0, union);
+        for (old, new) in [
+            (&mut shape.wire_colors, wire_colors),
+            (&mut shape.edge_colors, edge_colors),
+        ] {
+            if let Some((old, new)) = old.as_mut().zip(new) {
+                old[vertices.clone()].copy_from_slice(&new[vertices.clone()]);
+            }
+        }
+        if let Some((old, new)) = shape.edge_masks.as_mut().zip(edge_masks) {
+            old[vertices.clone()].copy_from_slice(&new[vertices.clone()]);
+        }
+
+        let mut moved: Vec<u32> = vertices
+            .clone()
+            .flat_map(|v| shape.vertex_triangles(v))
+            .copied()
+            .collect();
+        moved.sort_unstable();
+        moved.dedup();
+        let linked = shape.adjacency.reweld(positions, &shape.triangles, &moved);
+        let triangles = match linked.first().zip(linked.last()) {
+            Some((first, last)) => *first as usize..*last as usize + 1,
+            None => 0..0,
+        };
+
+        Some(FaceUpdate {
+            vertex_data: mesh.get_vertex_buffer_data(),
+            wire_colors: wire_colors.map(|colors| colors[vertices.clone()].to_vec()),
+            vertices,
+            masks: shape.masks(positions, triangles.clone()),
+            creases: shape.creases(positions, triangles.clone()),
+            neighbors: shape.neighbors(triangles.clone()),
+            edge_colors: shape.edge_colors(triangles.clone()),
+            triangles,
+        })
+    }
+
+    /// Apply the changes to face data that is still waiting to be uploaded
+    fn apply(self, face_data: &mut FaceData) {
+        face_data.vertex_data = self.vertex_data;
+        if let Some((old, new)) = face_data.wire_colors.as_mut().zip(self.wire_colors) {
+            old[self.vertices].copy_from_slice(&new);
+        }
+        let triangles = self.triangles;
+        face_data.masks[triangles.clone()].copy_from_slice(&self.masks);
+        face_data.creases[triangles.clone()].copy_from_slice(&self.creases);
+        face_data.neighbors[triangles.clone()].copy_from_slice(&self.neighbors);
+        if let Some((old, new)) = face_data.edge_colors.as_mut().zip(self.edge_colors) {
+            old[3 * triangles.start..3 * triangles.end].copy_from_slice(&new);
+        }
+    }
+}
+
+/// The [`FaceData`] of the meshes added or modified since the last extraction,
+/// the [`FaceUpdate`]s of those modified in place, and the meshes removed
+///
+/// The main world's copy is filled by [`build_face_data`] and drained into the
+/// render world's copy each extraction.
+#[derive(Resource, Default)]
+pub struct ExtractedFaceData {
+    added: Vec<(AssetId<Mesh>, FaceData)>,
+    updated: Vec<(AssetId<Mesh>, FaceUpdate)>,
+    removed: Vec<AssetId<Mesh>>,
+}
+
+/// The face data of a mesh on the GPU
 pub struct PosBuffer {
+    /// The mesh's vertex buffer, which is also the one its [`GpuMesh`] is
+    /// drawn from
+    ///
+    /// Bevy creates vertex buffers with only [`BufferUsages::VERTEX`], so this
+    /// one, which can also be bound as storage, replaces it each time the mesh
+    /// is uploaded, and the mesh's vertices are only on the GPU once.
     pub buffer: Buffer,
+    /// A `UVec4` uniform of the stride between vertices and the offset of the
+    /// position in each vertex of [`PosBuffer::buffer`], in `f32`s, as laid
+    /// out by the [`GpuMesh`], followed by the vertex count and whether the
+    /// triangles are read through [`PosBuffer::index_buffer`]
+    ///
+    /// The shaders read positions through it because the
+    /// [`DeformedFaces`](crate::deform::DeformedFaces) of an entity are laid
+    /// out as `Vec4`s instead.
+    pub vertex_layout: Buffer,
     pub vertex_count: usize,
+    /// The mesh's triangles as `u32` triangle list indices, or its segments as
+    /// line list indices, if it has indices or is a strip
+    pub index_buffer: Option<Buffer>,
+    pub triangle_count: usize,
+    pub segment_count: usize,
+    /// The [`ATTRIBUTE_EDGE_MASK`](crate::mesh::ATTRIBUTE_EDGE_MASK) of each
+    /// triangle
+    pub edge_mask_buffer: Buffer,
+    /// The crease angle of each edge of each triangle
+    pub crease_buffer: Buffer,
+    /// The triangle across each edge of each triangle
+    pub neighbor_buffer: Buffer,
+    /// The lengths of the edges and the area of each triangle, in model units,
+    /// written by the [`FaceComputeNode`]
+    pub face_buffer: Buffer,
+    /// The [`ATTRIBUTE_WIRE_COLOR`] of each vertex, if the mesh has it
+    pub wire_color_buffer: Option<Buffer>,
+    /// The [`ATTRIBUTE_EDGE_COLOR`](crate::mesh::ATTRIBUTE_EDGE_COLOR) of each
+    /// edge of each triangle, if the mesh has it
+    pub edge_color_buffer: Option<Buffer>,
+    /// The joint indices, as `UVec4`s, and joint weights of each vertex, if the
+    /// mesh is skinned
+    pub joint_buffers: Option<(Buffer, Buffer)>,
+    /// The mesh's morph targets, if it has them
+    pub morph_targets: Option<TextureView>,
+}
+
+impl PosBuffer {
+    /// Upload a mesh's face data, along with the morph targets of its
+    /// `gpu_mesh`, or `None` if `gpu_mesh` wasn't uploaded from the same
+    /// vertices
+    fn new(render_device: &RenderDevice, face_data: &FaceData, gpu_mesh: &GpuMesh) -> Option<Self> {
+        if gpu_mesh.vertex_buffer.size() != face_data.vertex_data.len() as u64 {
+            return None;
+        }
+        let position = gpu_mesh
+            .layout
+            .0
+            .get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])
+            .ok()?;
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("wireframe_vertex_buffer"),
+            contents: &face_data.vertex_data,
+            usage: BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        // wgpu aligns vertex strides and `Float32x3` offsets to 4 bytes.
+        let vertex_layout = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("vertex_layout_buffer"),
+            contents: bytemuck::cast_slice(&[UVec4::new(
+                position.array_stride as u32 / 4,
+                position.attributes[0].offset as u32 / 4,
+                face_data.vertex_count as u32,
+                face_data.indices.is_some().into(),
+            )]),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let index_buffer = face_data.indices.as_ref().map(|indices| {
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("face_index_buffer"),
+                contents: bytemuck::cast_slice(indices.as_slice()),
+                usage: BufferUsages::STORAGE,
+            })
+        });
+        let edge_mask_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("edge_mask_buffer"),
+            contents: bytemuck::cast_slice(face_data.masks.as_slice()),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let crease_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("crease_buffer"),
+            contents: bytemuck::cast_slice(face_data.creases.as_slice()),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let neighbor_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("neighbor_buffer"),
+            contents: bytemuck::cast_slice(face_data.neighbors.as_slice()),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let wire_color_buffer = face_data.wire_colors.as_ref().map(|colors| {
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("wire_color_buffer"),
+                contents: bytemuck::cast_slice(colors.as_slice()),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            })
+        });
+        let edge_color_buffer = face_data.edge_colors.as_ref().map(|colors| {
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("edge_color_buffer"),
+                contents: bytemuck::cast_slice(colors.as_slice()),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            })
+        });
+
+        let joint_buffers = face_data.joints.as_ref().map(|(indices, weights)| {
+            let index_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("joint_index_buffer"),
+                contents: bytemuck::cast_slice(indices.as_slice()),
+                usage: BufferUsages::STORAGE,
+            });
+            let weight_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("joint_weight_buffer"),
+                contents: bytemuck::cast_slice(weights.as_slice()),
+                usage: BufferUsages::STORAGE,
+            });
+            (index_buffer, weight_buffer)
+        });
+
+        let face_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("face_compute"),
+            // Line meshes have no triangles, but the buffer must not be empty.
+            size: (std::mem::size_of::<Vec4>() * face_data.triangle_count.max(1)) as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        Some(PosBuffer {
+            buffer,
+            vertex_layout,
+            vertex_count: face_data.vertex_count,
+            index_buffer,
+            triangle_count: face_data.triangle_count,
+            segment_count: face_data.segment_count,
+            edge_mask_buffer,
+            crease_buffer,
+            neighbor_buffer,
+            face_buffer,
+            wire_color_buffer,
+            edge_color_buffer,
+            joint_buffers,
+            morph_targets: gpu_mesh.morph_targets.clone(),
+        })
+    }
+
+    /// Write a [`FaceUpdate`] into the buffers, and take the morph targets of
+    /// the `gpu_mesh` uploaded again
+    fn update(&mut self, render_queue: &RenderQueue, update: &FaceUpdate, gpu_mesh: &GpuMesh) {
+        let vertex = update.vertices.start;
+        let triangle = update.triangles.start;
+        write_from(render_queue, &self.buffer, 0, &update.vertex_data);
+        if let Some((buffer, colors)) = self
+            .wire_color_buffer
+            .as_ref()
+            .zip(update.wire_colors.as_ref())
+        {
+            write_from(render_queue, buffer, vertex, colors);
+        }
+        write_from(
+            render_queue,
+            &self.edge_mask_buffer,
+            triangle,
+            &update.masks,
+        );
+        write_from(render_queue, &self.crease_buffer, triangle, &update.creases);
+        write_from(
+            render_queue,
+            &self.neighbor_buffer,
+            triangle,
+            &update.neighbors,
+        );
+        if let Some((buffer, colors)) = self
+            .edge_color_buffer
+            .as_ref()
+            .zip(update.edge_colors.as_ref())
+        {
+            write_from(render_queue, buffer, 3 * triangle, colors);
+        }
+        self.morph_targets.clone_from(&gpu_mesh.morph_targets);
+    }
+
+    /// The vertices [`DrawMeshFaces`](crate::wireframe2d::DrawMeshFaces)
+    /// pulls from storage: three for each triangle, or six for the quad of
+    /// each line segment
+    pub fn pulled_vertex_count(&self) -> u32 {
+        (3 * self.triangle_count + 6 * self.segment_count) as u32
+    }
+
+    /// Draw the `gpu_mesh` from [`PosBuffer::buffer`], if bevy uploaded it
+    /// again into a new vertex buffer of the same size
+    fn share_vertex_buffer(&self, gpu_mesh: &mut GpuMesh) {
+        if gpu_mesh.vertex_buffer.id() != self.buffer.id()
+            && gpu_mesh.vertex_buffer.size() == self.buffer.size()
+        {
+            gpu_mesh.vertex_buffer = self.buffer.clone();
+        }
+    }
+
+    /// The index buffer to bind, or the vertex buffer as a stand-in for
+    /// meshes without indices where the shader never reads it
+    pub fn index_binding(&self) -> &Buffer {
+        self.index_buffer.as_ref().unwrap_or(&self.buffer)
+    }
+
+    /// The wire color buffer to bind, or the vertex buffer as a stand-in
+    pub fn wire_color_binding(&self) -> &Buffer {
+        self.wire_color_buffer.as_ref().unwrap_or(&self.buffer)
+    }
+
+    /// The edge color buffer to bind, or the vertex buffer as a stand-in
+    pub fn edge_color_binding(&self) -> &Buffer {
+        self.edge_color_buffer.as_ref().unwrap_or(&self.buffer)
+    }
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct FaceBuffer {
-    buffer: Buffer,
+/// Write `values` into `buffer`, starting at the value at index `first`
+fn write_from<T: bytemuck::Pod>(
+    render_queue: &RenderQueue,
+    buffer: &Buffer,
+    first: usize,
+    values: &[T],
+) {
+    if !values.is_empty() {
+        render_queue.write_buffer(
+            buffer,
+            (first * std::mem::size_of::<T>()) as u64,
+            bytemuck::cast_slice(values),
+        );
+    }
+}
+
+/// The [`PosBuffer`] of each mesh, and the face data waiting for its mesh to be
+/// uploaded
+#[derive(Resource, Default)]
+pub struct PosBuffers {
+    buffers: HashMap<AssetId<Mesh>, PosBuffer>,
+    waiting: HashMap<AssetId<Mesh>, FaceData>,
+    /// The triangles of each mesh updated in place this frame, whose face
+    /// data is computed again
+    updated: Vec<(AssetId<Mesh>, Range<usize>)>,
+}
+
+impl PosBuffers {
+    pub fn get(&self, id: impl Into<AssetId<Mesh>>) -> Option<&PosBuffer> {
+        self.buffers.get(&id.into())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId<Mesh>, &PosBuffer)> {
+        self.buffers.iter().map(|(id, buffer)| (*id, buffer))
+    }
 }
 
+/// Makes the face data of the meshes drawn as wireframes with the app's
+/// [`WireframeBackend`]
 pub struct FacePlugin;
 
 impl Plugin for FacePlugin {
     fn build(&self, app: &mut App) {
         embedded_asset!(app, "face_compute.wgsl");
-        app.add_plugins(RenderAssetPlugin::<PosBuffer, GpuImage>::default());
+        app.init_resource::<ExtractedFaceData>()
+            .init_resource::<MeshShapes>()
+            .init_resource::<WireframeMeshes>()
+            .init_resource::<BakedMeshes>()
+            .configure_sets(Last, CollectWireframeMeshes.after(AssetEvents));
+        app.sub_app_mut(RenderApp)
+            .init_resource::<ExtractedFaceData>()
+            .init_resource::<PosBuffers>()
+            .init_resource::<FaceComputes>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        // The backend is chosen once the render device is known.
+        if init_backend(app) == WireframeBackend::VertexAttributes {
+            app.add_systems(Last, bake_face_attributes.after(CollectWireframeMeshes));
+            return;
+        }
+        app.add_systems(Last, build_face_data.after(CollectWireframeMeshes));
 
         let render_app = app.sub_app_mut(RenderApp);
-        let node = FaceComputeNode::from_world(render_app.world_mut());
-        render_app.add_systems(
-            Render,
-            (
-                prepare_dist_buffers.in_set(RenderSet::PrepareResources),
-                prepare_bind_group.in_set(RenderSet::PrepareBindGroups),
-            ),
-        );
+        render_app
+            .add_systems(ExtractSchedule, extract_face_data)
+            .add_systems(
+                Render,
+                (
+                    prepare_pos_buffers
+                        .in_set(RenderSet::PrepareAssets)
+                        .after(prepare_assets::<GpuMesh>),
+                    prepare_bind_group.in_set(RenderSet::PrepareBindGroups),
+                ),
+            )
+            // Register our custom pipeline
+            .init_resource::<FacePipeline>();
 
         let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
-        render_graph.add_node(FaceLabel, node);
+        render_graph.add_node(FaceLabel, FaceComputeNode);
         render_graph.add_node_edge(FaceLabel, bevy::render::graph::CameraDriverLabel);
     }
-
-    fn finish(&self, app: &mut App) {
-        // Register our custom pipeline
-        app.sub_app_mut(RenderApp).init_resource::<FacePipeline>();
-    }
 }
 
-fn prepare_dist_buffers(
-    mut commands: Commands,
-    meshes: Res<RenderAssets<GpuMesh>>,
-    query: Query<(Entity, &Mesh2dHandle)>,
-    render_device: Res<RenderDevice>,
+/// Build the [`FaceData`] of each mesh in [`WireframeMeshes`] while it is still
+/// in the main world, and again when it is modified
+///
+/// Like its [`GpuMesh`], a mesh's face data is kept until the mesh is unused,
+/// not when it is removed from the main world.
+///
+/// The shape of a mesh is kept while the mesh is in the main world. When it is
+/// modified without changing its [`MeshLayout`], only a [`FaceUpdate`] of the
+/// triangles around the vertices that changed is sent, and written into the
+/// face buffers in place instead of making them again.
+fn build_face_data(
+    mut events: EventReader<AssetEvent<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    mut wireframe_meshes: ResMut<WireframeMeshes>,
+    mut shapes: ResMut<MeshShapes>,
+    mut extracted: ResMut<ExtractedFaceData>,
 ) {
-    for (entity, handle) in &query {
-        let mesh_asset_id = handle.0.id();
-        let Some(gpu_mesh) = meshes.get(mesh_asset_id) else {
-            warn!("no gpu mesh");
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } => wireframe_meshes.insert_render_world_mesh(*id, &meshes),
+            AssetEvent::Modified { id } if wireframe_meshes.is_built(*id) => {
+                let Some(mesh) = meshes.get(*id) else {
+                    continue;
+                };
+                if let Some(shape) = shapes.0.get_mut(id) {
+                    if shape.layout == MeshLayout::new(mesh) {
+                        if let Some(update) = FaceUpdate::new(mesh, shape) {
+                            extracted.updated.push((*id, update));
+                        }
+                        continue;
+                    }
+                }
+                let Some((face_data, shape)) = FaceData::with_shape(*id, mesh) else {
+                    warn!("Skipping wireframe of mesh {id:?} without Float32x3 positions");
+                    continue;
+                };
+                keep_shape(&mut shapes, *id, mesh, shape);
+                extracted.added.retain(|(added, _)| added != id);
+                extracted.updated.retain(|(updated, _)| updated != id);
+                extracted.added.push((*id, face_data));
+            }
+            AssetEvent::Removed { id } => {
+                shapes.0.remove(id);
+            }
+            AssetEvent::Unused { id } => {
+                wireframe_meshes.forget(*id);
+                shapes.0.remove(id);
+                extracted.added.retain(|(added, _)| added != id);
+                extracted.updated.retain(|(updated, _)| updated != id);
+                extracted.removed.push(*id);
+            }
+            _ => {}
+        }
+    }
+
+    for (id, mesh) in wireframe_meshes.take_new(&meshes) {
+        let Some((face_data, shape)) = FaceData::with_shape(id, mesh) else {
+            warn!("Skipping wireframe of mesh {id:?} without Float32x3 positions");
             continue;
         };
-        let vertex_count = gpu_mesh.vertex_count as usize;
-        let buffer = render_device.create_buffer(&BufferDescriptor {
-            label: Some("face_compute"),
-            size: (std::mem::size_of::<Vec4>() * vertex_count / 3) as u64,
-            usage: BufferUsages::STORAGE | BufferUsages::VERTEX,
-            mapped_at_creation: false,
-        });
-        commands.entity(entity).insert(FaceBuffer { buffer });
+        keep_shape(&mut shapes, id, mesh, shape);
+        extracted.added.push((id, face_data));
     }
 }
 
-impl RenderAsset for PosBuffer {
-    type SourceAsset = Mesh;
-    type Param = (SRes<RenderDevice>,);
-
-    /// We add MAIN_WORLD to usage because we need the mesh to be accessible in
-    /// main world for at least the initialization. It would be nice to not have
-    /// to have this.
-    #[inline]
-    fn asset_usage(mesh: &Self::SourceAsset) -> RenderAssetUsages {
-        mesh.asset_usage | RenderAssetUsages::MAIN_WORLD
+/// Keep the shape of a mesh that stays in the main world, where it can be
+/// modified
+fn keep_shape(shapes: &mut MeshShapes, id: AssetId<Mesh>, mesh: &Mesh, shape: MeshShape) {
+    if mesh.asset_usage.contains(RenderAssetUsages::MAIN_WORLD) {
+        shapes.0.insert(id, shape);
+    } else {
+        shapes.0.remove(&id);
     }
+}
 
-    fn byte_len(mesh: &Self::SourceAsset) -> Option<usize> {
-        Some(mesh.count_vertices() * std::mem::size_of::<Vec4>())
-    }
+/// Move the main world's [`ExtractedFaceData`] into the render world
+fn extract_face_data(mut main_world: ResMut<MainWorld>, mut extracted: ResMut<ExtractedFaceData>) {
+    let mut main_extracted = main_world.resource_mut::<ExtractedFaceData>();
+    extracted.added.append(&mut main_extracted.added);
+    extracted.updated.append(&mut main_extracted.updated);
+    extracted.removed.append(&mut main_extracted.removed);
+}
 
-    fn prepare_asset(
-        mesh: Self::SourceAsset,
-        (render_device,): &mut SystemParamItem<Self::Param>,
-    ) -> Result<Self, PrepareAssetError<Self::SourceAsset>> {
-        let Some(VertexAttributeValues::Float32x3(positions)) =
-            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+/// Upload the extracted face data of each mesh once its [`GpuMesh`] is ready,
+/// write the updates of the meshes modified in place, and draw each mesh from
+/// its [`PosBuffer::buffer`]
+fn prepare_pos_buffers(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut render_meshes: ResMut<RenderAssets<GpuMesh>>,
+    mut extracted: ResMut<ExtractedFaceData>,
+    mut pos_buffers: ResMut<PosBuffers>,
+) {
+    let pos_buffers = pos_buffers.as_mut();
+    for id in extracted.removed.drain(..) {
+        pos_buffers.buffers.remove(&id);
+        pos_buffers.waiting.remove(&id);
+    }
+    pos_buffers.waiting.extend(extracted.added.drain(..));
+    for (id, update) in extracted.updated.drain(..) {
+        if let Some(face_data) = pos_buffers.waiting.get_mut(&id) {
+            update.apply(face_data);
+            continue;
+        }
+        let (Some(pos_buffer), Some(gpu_mesh)) =
+            (pos_buffers.buffers.get_mut(&id), render_meshes.get(id))
         else {
-            warn!("no position vertices");
-            return Err(PrepareAssetError::RetryNextUpdate(mesh));
+            continue;
         };
-        let v_pos_4: Vec<[f32; 4]> = positions.iter().map(|x| crate::pad(*x)).collect();
-
-        let vertex_count = mesh.count_vertices();
-
-        let pos_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-            label: Some("pos_buffer"),
-            contents: bytemuck::cast_slice(v_pos_4.as_slice()),
-            usage: BufferUsages::STORAGE,
-        });
-
-        Ok(PosBuffer {
-            vertex_count,
-            buffer: pos_buffer,
-        })
+        pos_buffer.update(&render_queue, &update, gpu_mesh);
+        if !update.triangles.is_empty() {
+            pos_buffers.updated.push((id, update.triangles));
+        }
+    }
+    // A mesh modified since its face data was built is uploaded again first.
+    pos_buffers.waiting.retain(|id, face_data| {
+        let Some(pos_buffer) = render_meshes
+            .get(*id)
+            .and_then(|gpu_mesh| PosBuffer::new(&render_device, face_data, gpu_mesh))
+        else {
+            return true;
+        };
+        pos_buffers.buffers.insert(*id, pos_buffer);
+        false
+    });
+    for (id, pos_buffer) in &pos_buffers.buffers {
+        if let Some(gpu_mesh) = render_meshes.get_mut(*id) {
+            pos_buffer.share_vertex_buffer(gpu_mesh);
+        }
     }
 }
 
@@ -162,107 +1042,249 @@ impl FromWorld for FacePipeline {
             &BindGroupLayoutEntries::sequential(
                 ShaderStages::COMPUTE,
                 (
-                    storage_buffer_read_only::<Vec<Vec4>>(false),
+                    storage_buffer_read_only::<Vec<f32>>(false),
                     storage_buffer::<Vec<Vec4>>(false),
+                    storage_buffer_read_only::<Vec<u32>>(false),
+                    uniform_buffer::<UVec4>(false),
+                    uniform_buffer::<UVec4>(false),
                 ),
             ),
         );
 
-        let shader_defs = vec!["MODEL_DIST".into()];
         let shader = world.load_asset::<Shader>("embedded://bevy_wireframe/face_compute.wgsl");
         let pipeline_cache = world.resource::<PipelineCache>();
         let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label: Some("Face compute shader".into()),
             layout: vec![layout.clone()],
             push_constant_ranges: Vec::new(),
+            shader: shader.clone(),
+            shader_defs: vec![
+                "MODEL_DIST".into(),
+                ShaderDefVal::UInt("WORKGROUP_SIZE".into(), WORKGROUP_SIZE),
+            ],
+            entry_point: "main".into(),
+        });
+        let indexed_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("Indexed face compute shader".into()),
+            layout: vec![layout.clone()],
+            push_constant_ranges: Vec::new(),
             shader,
-            shader_defs,
+            shader_defs: vec![
+                "MODEL_DIST".into(),
+                "INDEXED".into(),
+                ShaderDefVal::UInt("WORKGROUP_SIZE".into(), WORKGROUP_SIZE),
+            ],
             entry_point: "main".into(),
         });
-        FacePipeline { layout, pipeline }
-    }
-}
-
-pub struct FaceComputeNode {
-    query: QueryState<&'static FaceBinding>,
-}
-
-impl FromWorld for FaceComputeNode {
-    fn from_world(world: &mut World) -> Self {
-        Self {
-            query: QueryState::new(world),
+        FacePipeline {
+            layout,
+            pipeline,
+            indexed_pipeline,
         }
     }
 }
 
-fn prepare_bind_group(
-    mut commands: Commands,
+/// Computes the face data of newly prepared meshes
+pub struct FaceComputeNode;
+
+/// Queue the face compute of each mesh whose [`PosBuffer`] is new since the
+/// last frame, and of the triangles of those updated in place, and forget the
+/// meshes that were removed
+pub(crate) fn prepare_bind_group(
     pipeline: Res<FacePipeline>,
+    pipeline_cache: Res<PipelineCache>,
     render_device: Res<RenderDevice>,
-    pos_buffers: Res<RenderAssets<PosBuffer>>,
-    wireframe_mesh: Query<(Entity, &FaceBuffer), With<WireframeMesh2d>>,
-    mut wireframe_mesh_instances: ResMut<WireframeMesh2dInstances>,
+    mut pos_buffers: ResMut<PosBuffers>,
+    mut face_computes: ResMut<FaceComputes>,
 ) {
-    for (entity, dist_buffer) in wireframe_mesh.iter() {
-        let Some(RenderMesh2dInstance { mesh_asset_id, .. }) =
-            wireframe_mesh_instances.get_mut(&entity)
-        else {
-            warn!("no wireframe mesh 2d");
-            return;
-        };
-        let Some(pos_buffer) = pos_buffers.get(*mesh_asset_id) else {
-            warn!("no pos buffer");
-            return;
+    let face_computes = face_computes.as_mut();
+    face_computes.pending.clear();
+    face_computes.deforms.clear();
+    let updated = std::mem::take(&mut pos_buffers.updated);
+    // Wait for the pipelines, or the meshes would be marked computed without
+    // being dispatched.
+    if pipeline_cache
+        .get_compute_pipeline(pipeline.pipeline)
+        .zip(pipeline_cache.get_compute_pipeline(pipeline.indexed_pipeline))
+        .is_none()
+    {
+        return;
+    }
+    face_computes
+        .computed
+        .retain(|id, _| pos_buffers.get(*id).is_some());
+    // Meshes that haven't been computed yet are computed whole below.
+    for (id, triangles) in updated {
+        let Some(pos_buffer) = pos_buffers.get(id) else {
+            continue;
         };
-        let bind_group = render_device.create_bind_group(
-            None,
-            &pipeline.layout,
-            &BindGroupEntries::sequential((
-                pos_buffer.buffer.as_entire_buffer_binding(),
-                dist_buffer.buffer.as_entire_buffer_binding(),
-            )),
-        );
-        let vertex_count = pos_buffer.vertex_count;
-        commands.entity(entity).insert(FaceBinding {
-            bind_group,
-            vertex_count,
-            dist_buffer: dist_buffer.buffer.clone(),
-        });
+        if face_computes.computed.get(&id) != Some(&pos_buffer.face_buffer.id()) {
+            continue;
+        }
+        face_computes.pending.push(FaceBinding::new(
+            &render_device,
+            &pipeline,
+            pos_buffer,
+            &pos_buffer.buffer,
+            &pos_buffer.vertex_layout,
+            &pos_buffer.face_buffer,
+            triangles,
+        ));
+    }
+    for (id, pos_buffer) in pos_buffers.iter() {
+        let face_buffer_id = pos_buffer.face_buffer.id();
+        if face_computes.computed.get(&id) == Some(&face_buffer_id) {
+            continue;
+        }
+        face_computes.pending.push(FaceBinding::new(
+            &render_device,
+            &pipeline,
+            pos_buffer,
+            &pos_buffer.buffer,
+            &pos_buffer.vertex_layout,
+            &pos_buffer.face_buffer,
+            0..pos_buffer.triangle_count,
+        ));
+        face_computes.computed.insert(id, face_buffer_id);
     }
 }
 
 impl Node for FaceComputeNode {
-    fn update(&mut self, world: &mut World) {
-        self.query.update_archetypes(world);
-    }
-
-    fn output(&self) -> Vec<SlotInfo> {
-        vec![SlotInfo::new("face", SlotType::Buffer)]
-    }
-
     fn run(
         &self,
-        graph: &mut RenderGraphContext,
+        _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        for wireframe_binding in self.query.iter_manual(world) {
-            let bind_group = &wireframe_binding.bind_group;
-            let pipeline_cache = world.resource::<PipelineCache>();
-            let pipeline = world.resource::<FacePipeline>();
-
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = world.resource::<FacePipeline>();
+        let max_per_dimension = world
+            .resource::<RenderDevice>()
+            .limits()
+            .max_compute_workgroups_per_dimension;
+        let face_computes = world.resource::<FaceComputes>();
+        for deform_binding in &face_computes.deforms {
+            if deform_binding.vertex_count == 0 {
+                continue;
+            }
+            let Some(deform_pipeline) =
+                pipeline_cache.get_compute_pipeline(deform_binding.pipeline)
+            else {
+                continue;
+            };
+            let mut pass = render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor::default());
+            pass.set_bind_group(0, &deform_binding.bind_group, &[]);
+            pass.set_pipeline(deform_pipeline);
+            let (x, y) = dispatch_size(deform_binding.vertex_count as u32, max_per_dimension);
+            pass.dispatch_workgroups(x, y, 1);
+        }
+        for face_binding in &face_computes.pending {
+            if face_binding.triangle_count == 0 {
+                continue;
+            }
             let mut pass = render_context
                 .command_encoder()
                 .begin_compute_pass(&ComputePassDescriptor::default());
 
-            let update_pipeline = pipeline_cache
-                .get_compute_pipeline(pipeline.pipeline)
-                .unwrap();
-            pass.set_bind_group(0, bind_group, &[]);
+            let pipeline_id = if face_binding.indexed {
+                pipeline.indexed_pipeline
+            } else {
+                pipeline.pipeline
+            };
+            let update_pipeline = pipeline_cache.get_compute_pipeline(pipeline_id).unwrap();
+            pass.set_bind_group(0, &face_binding.bind_group, &[]);
             pass.set_pipeline(update_pipeline);
-            pass.dispatch_workgroups((wireframe_binding.vertex_count / 3) as u32, 1, 1);
-            graph.set_output("face", wireframe_binding.dist_buffer.clone())?;
+            let (x, y) = dispatch_size(face_binding.triangle_count as u32, max_per_dimension);
+            pass.dispatch_workgroups(x, y, 1);
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::Indices;
+
+    /// A row of three unit quads, two indexed triangles each
+    fn quads(positions: Vec<[f32; 3]>) -> Mesh {
+        let indices = (0..3)
+            .flat_map(|q| [q, q + 1, q + 5, q, q + 5, q + 4])
+            .collect();
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+
+    fn quad_positions() -> Vec<[f32; 3]> {
+        (0..8)
+            .map(|v| [(v % 4) as f32, (v / 4) as f32, 0.0])
+            .collect()
+    }
+
+    #[test]
+    fn dispatch_size_splits_rows() {
+        assert_eq!(dispatch_size(0, 65535), (0, 0));
+        assert_eq!(dispatch_size(WORKGROUP_SIZE, 65535), (1, 1));
+        assert_eq!(dispatch_size(WORKGROUP_SIZE + 1, 65535), (2, 1));
+        assert_eq!(dispatch_size(10 * WORKGROUP_SIZE, 4), (4, 3));
+    }
+
+    #[test]
+    fn changed_and_union_ranges() {
+        assert_eq!(changed(&[1, 2, 3, 4], &[1, 5, 3, 6]), 1..4);
+        assert_eq!(changed(&[1, 2], &[1, 2]), 0..0);
+        assert_eq!(union(0..0, 2..3), 2..3);
+        assert_eq!(union(1..2, 0..0), 1..2);
+        assert_eq!(union(1..2, 4..6), 1..6);
+    }
+
+    #[test]
+    fn mesh_layout_compares_index_values() {
+        let mesh = quads(quad_positions());
+        let mut flipped = mesh.clone();
+        if let Some(Indices::U32(indices)) = flipped.indices_mut() {
+            indices.swap(1, 2);
+        }
+        assert!(MeshLayout::new(&mesh) == MeshLayout::new(&mesh.clone()));
+        assert!(MeshLayout::new(&mesh) != MeshLayout::new(&flipped));
+    }
+
+    #[test]
+    fn face_update_of_an_unchanged_mesh_is_empty() {
+        let mesh = quads(quad_positions());
+        let (mut shape, _) = MeshShape::new(&mesh).unwrap();
+        let update = FaceUpdate::new(&mesh, &mut shape).unwrap();
+        assert!(update.vertices.is_empty());
+        assert!(update.triangles.is_empty());
+    }
+
+    #[test]
+    fn face_update_matches_a_rebuild() {
+        let (mut face_data, mut shape) =
+            FaceData::with_shape(AssetId::default(), &quads(quad_positions())).unwrap();
+        assert!(face_data.masks.iter().all(|mask| mask.count_ones() == 1));
+
+        // Lifting a corner of the last quad folds it along its diagonal.
+        let mut positions = quad_positions();
+        positions[7][2] = 1.0;
+        let mesh = quads(positions);
+        let update = FaceUpdate::new(&mesh, &mut shape).unwrap();
+        assert_eq!(update.vertices, 7..8);
+        // The folded triangles, and the neighbor across their other edge
+        assert_eq!(update.triangles, 2..6);
+        update.apply(&mut face_data);
+
+        let (rebuilt, _) = FaceData::with_shape(AssetId::default(), &mesh).unwrap();
+        assert_eq!(face_data.vertex_data, rebuilt.vertex_data);
+        assert_eq!(face_data.masks, rebuilt.masks);
+        assert_eq!(face_data.creases, rebuilt.creases);
+        assert_eq!(face_data.neighbors, rebuilt.neighbors);
+        assert_eq!(&face_data.masks[4..], &[0, 0]);
+    }
+}
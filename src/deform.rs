@@ -0,0 +1,432 @@
+use crate::{
+    backend::{init_backend, WireframeBackend},
+    compute::{
+        prepare_bind_group, DeformBinding, FaceBinding, FaceComputes, FaceMesh, FacePipeline,
+        PosBuffer, PosBuffers, WORKGROUP_SIZE,
+    },
+    style::{NoWireframe, WireframeSettings},
+    wireframe3d::WireframeMesh3d,
+};
+use bevy::{
+    app::{App, Plugin},
+    asset::{embedded_asset, Assets, DirectAssetAccessExt},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, Or, With, Without},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+        world::{FromWorld, World},
+    },
+    math::{Mat4, UVec4, Vec4},
+    render::{
+        mesh::{
+            morph::MeshMorphWeights,
+            skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+        },
+        render_resource::{
+            binding_types::{storage_buffer, storage_buffer_read_only, texture_3d, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
+            BufferDescriptor, BufferId, BufferInitDescriptor, BufferUsages,
+            CachedComputePipelineId, ComputePipelineDescriptor, PipelineCache, Shader,
+            ShaderDefVal, ShaderStages, TextureSampleType, TextureView, TextureViewId,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        texture::FallbackImage,
+        view::ViewVisibility,
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    transform::components::GlobalTransform,
+    utils::EntityHashMap,
+};
+
+/// The pose of a skinned or morphed wireframe entity, extracted every frame
+#[derive(Component, Clone, Debug, Default)]
+pub struct WireframeDeformation {
+    /// Each joint's transform from the bind pose into the entity's model
+    /// space. Empty when the entity isn't skinned.
+    pub joints: Vec<Mat4>,
+    /// The weight of each morph target.
+    pub morph_weights: Vec<f32>,
+}
+
+/// The deformed positions and face data of one entity, which replace its
+/// mesh's [`PosBuffer`] ones in its wireframe bind group
+pub struct DeformedFaces {
+    /// The deformed model space positions, as `Vec4`s
+    pub positions: Buffer,
+    /// The layout of [`DeformedFaces::positions`], like
+    /// [`PosBuffer::vertex_layout`]
+    pub vertex_layout: Buffer,
+    /// Like [`PosBuffer::face_buffer`], measured on the deformed positions
+    pub face_buffer: Buffer,
+    /// The joint matrices, written every frame
+    joint_buffer: Buffer,
+    joint_capacity: usize,
+    /// The morph target weights, written every frame
+    morph_weight_buffer: Buffer,
+    morph_weight_capacity: usize,
+    /// A `UVec4` uniform of whether the entity is skinned and its number of
+    /// morph targets, written every frame
+    deform_buffer: Buffer,
+    deform_bind_group: BindGroup,
+    face_binding: FaceBinding,
+    /// The mesh buffer these were made for
+    source: BufferId,
+    /// The morph targets bound in [`DeformedFaces::deform_bind_group`]
+    morph_targets: Option<TextureViewId>,
+}
+
+/// The [`DeformedFaces`] of each deformed wireframe entity
+///
+/// The buffers and bind groups are kept while the entity stays deformed and
+/// visible, and the pose is written into them every frame. They are made
+/// again when the entity's mesh is, or when its pose outgrows them.
+#[derive(Resource, Default)]
+pub struct DeformedFaceBuffers(EntityHashMap<Entity, DeformedFaces>);
+
+impl DeformedFaceBuffers {
+    pub fn get(&self, entity: Entity) -> Option<&DeformedFaces> {
+        self.0.get(&entity)
+    }
+}
+
+#[derive(Resource)]
+pub struct DeformPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for DeformPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(
+            "Deform",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    storage_buffer_read_only::<Vec<f32>>(false),
+                    uniform_buffer::<UVec4>(false),
+                    storage_buffer::<Vec<Vec4>>(false),
+                    storage_buffer_read_only::<Vec<UVec4>>(false),
+                    storage_buffer_read_only::<Vec<Vec4>>(false),
+                    storage_buffer_read_only::<Vec<Mat4>>(false),
+                    storage_buffer_read_only::<Vec<f32>>(false),
+                    texture_3d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<UVec4>(false),
+                ),
+            ),
+        );
+
+        let shader = world.load_asset::<Shader>("embedded://bevy_wireframe/deform_compute.wgsl");
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("Deform compute shader".into()),
+            layout: vec![layout.clone()],
+            push_constant_ranges: Vec::new(),
+            shader,
+            shader_defs: vec![ShaderDefVal::UInt("WORKGROUP_SIZE".into(), WORKGROUP_SIZE)],
+            entry_point: "main".into(),
+        });
+        DeformPipeline { layout, pipeline }
+    }
+}
+
+/// Keeps the wireframes of skinned and morphed 3d meshes on their animated
+/// surface
+///
+/// Each frame the positions of every deformed wireframe entity are skinned
+/// and morphed into its model space by a compute pass, and its face data is
+/// measured on them, so the wire width doesn't swim as the mesh moves.
+pub struct DeformPlugin;
+
+impl Plugin for DeformPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "deform_compute.wgsl");
+
+        app.sub_app_mut(RenderApp)
+            .init_resource::<DeformedFaceBuffers>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        // Without compute shaders, deformed entities are drawn in their bind
+        // pose.
+        if init_backend(app) != WireframeBackend::Compute {
+            return;
+        }
+        app.sub_app_mut(RenderApp)
+            .add_systems(ExtractSchedule, extract_wireframe_deformations)
+            .add_systems(
+                Render,
+                prepare_deformed_faces
+                    .in_set(RenderSet::PrepareBindGroups)
+                    .after(prepare_bind_group),
+            )
+            .init_resource::<DeformPipeline>();
+    }
+}
+
+/// Extract the joints and morph weights of the skinned and morphed wireframe
+/// entities
+#[allow(clippy::type_complexity)]
+pub fn extract_wireframe_deformations(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    settings: Extract<Res<WireframeSettings>>,
+    query: Extract<
+        Query<
+            (
+                Entity,
+                &ViewVisibility,
+                &GlobalTransform,
+                Option<&SkinnedMesh>,
+                Option<&MeshMorphWeights>,
+                Has<WireframeMesh3d>,
+            ),
+            (
+                Or<(With<SkinnedMesh>, With<MeshMorphWeights>)>,
+                Without<NoWireframe>,
+            ),
+        >,
+    >,
+    joint_query: Extract<Query<&GlobalTransform>>,
+    inverse_bindposes: Extract<Res<Assets<SkinnedMeshInverseBindposes>>>,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, view_visibility, transform, skin, morph_weights, marked) in &query {
+        if !(marked || settings.global) || !view_visibility.get() {
+            continue;
+        }
+        let mut joints = Vec::new();
+        if let Some(skin) = skin {
+            let Some(bindposes) = inverse_bindposes.get(&skin.inverse_bindposes) else {
+                continue;
+            };
+            // Bevy skins into world space, so bring the joints back into the
+            // entity's model space where the face data is measured.
+            let world_to_model = transform.compute_matrix().inverse();
+            let Some(skin_joints) = skin
+                .joints
+                .iter()
+                .zip(bindposes.iter())
+                .map(|(joint, bindpose)| {
+                    let joint = joint_query.get(*joint).ok()?;
+                    Some(world_to_model * joint.compute_matrix() * *bindpose)
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            joints = skin_joints;
+        }
+        values.push((
+            entity,
+            WireframeDeformation {
+                joints,
+                morph_weights: morph_weights
+                    .map(|weights| weights.weights().to_vec())
+                    .unwrap_or_default(),
+            },
+        ));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+/// Queue the deform and face computes of each deformed wireframe entity
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_deformed_faces(
+    pipeline: Res<DeformPipeline>,
+    face_pipeline: Res<FacePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    fallback_image: Res<FallbackImage>,
+    pos_buffers: Res<PosBuffers>,
+    mut face_computes: ResMut<FaceComputes>,
+    mut deformed_buffers: ResMut<DeformedFaceBuffers>,
+    query: Query<(Entity, &FaceMesh, &WireframeDeformation)>,
+) {
+    if pipeline_cache
+        .get_compute_pipeline(face_pipeline.pipeline)
+        .zip(pipeline_cache.get_compute_pipeline(face_pipeline.indexed_pipeline))
+        .is_none()
+    {
+        return;
+    }
+    // The buffers of entities that are no longer deformed are dropped with
+    // the previous map.
+    let mut previous_buffers = std::mem::take(&mut deformed_buffers.0);
+    for (entity, face_mesh, deformation) in &query {
+        let Some(pos_buffer) = pos_buffers.get(**face_mesh) else {
+            continue;
+        };
+        let deformed = match previous_buffers.remove(&entity) {
+            Some(deformed) if deformed.fits(pos_buffer, deformation) => deformed,
+            _ => DeformedFaces::new(
+                &render_device,
+                &pipeline,
+                &face_pipeline,
+                &fallback_image,
+                pos_buffer,
+                deformation,
+            ),
+        };
+        deformed.write(&render_queue, pos_buffer, deformation);
+        face_computes.deforms.push(DeformBinding {
+            bind_group: deformed.deform_bind_group.clone(),
+            pipeline: pipeline.pipeline,
+            vertex_count: pos_buffer.vertex_count,
+        });
+        face_computes.pending.push(deformed.face_binding.clone());
+        deformed_buffers.0.insert(entity, deformed);
+    }
+}
+
+impl DeformedFaces {
+    /// Create the buffers and bind groups a deformed entity using
+    /// `pos_buffer`'s mesh needs, with room for `deformation`
+    fn new(
+        render_device: &RenderDevice,
+        pipeline: &DeformPipeline,
+        face_pipeline: &FacePipeline,
+        fallback_image: &FallbackImage,
+        pos_buffer: &PosBuffer,
+        deformation: &WireframeDeformation,
+    ) -> Self {
+        let positions = render_device.create_buffer(&BufferDescriptor {
+            label: Some("deformed_pos_buffer"),
+            size: (std::mem::size_of::<Vec4>() * pos_buffer.vertex_count.max(1)) as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let vertex_layout = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("deformed_vertex_layout_buffer"),
+            contents: bytemuck::cast_slice(&[UVec4::new(
+                4,
+                0,
+                pos_buffer.vertex_count as u32,
+                pos_buffer.index_buffer.is_some().into(),
+            )]),
+            usage: BufferUsages::UNIFORM,
+        });
+        let face_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("deformed_face_compute"),
+            size: (std::mem::size_of::<Vec4>() * pos_buffer.triangle_count.max(1)) as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        // The buffers must not be empty.
+        let joint_capacity = deformation.joints.len().max(1);
+        let joint_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("deform_joint_buffer"),
+            size: (std::mem::size_of::<Mat4>() * joint_capacity) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let morph_weight_capacity = deformation.morph_weights.len().max(1);
+        let morph_weight_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("deform_morph_weight_buffer"),
+            size: (std::mem::size_of::<f32>() * morph_weight_capacity) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let deform_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("deform_buffer"),
+            size: std::mem::size_of::<UVec4>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let (joint_indices, joint_weights) = match &pos_buffer.joint_buffers {
+            Some((indices, weights)) => (indices, weights),
+            // Never read, as the deform uniform says the entity isn't skinned.
+            None => (&pos_buffer.buffer, &pos_buffer.buffer),
+        };
+        let morph_targets = pos_buffer
+            .morph_targets
+            .as_ref()
+            .unwrap_or(&fallback_image.d3.texture_view);
+        let deform_bind_group = render_device.create_bind_group(
+            None,
+            &pipeline.layout,
+            &BindGroupEntries::sequential((
+                pos_buffer.buffer.as_entire_buffer_binding(),
+                pos_buffer.vertex_layout.as_entire_buffer_binding(),
+                positions.as_entire_buffer_binding(),
+                joint_indices.as_entire_buffer_binding(),
+                joint_weights.as_entire_buffer_binding(),
+                joint_buffer.as_entire_buffer_binding(),
+                morph_weight_buffer.as_entire_buffer_binding(),
+                morph_targets,
+                deform_buffer.as_entire_buffer_binding(),
+            )),
+        );
+        let face_binding = FaceBinding::new(
+            render_device,
+            face_pipeline,
+            pos_buffer,
+            &positions,
+            &vertex_layout,
+            &face_buffer,
+            0..pos_buffer.triangle_count,
+        );
+        DeformedFaces {
+            positions,
+            vertex_layout,
+            face_buffer,
+            joint_buffer,
+            joint_capacity,
+            morph_weight_buffer,
+            morph_weight_capacity,
+            deform_buffer,
+            deform_bind_group,
+            face_binding,
+            source: pos_buffer.face_buffer.id(),
+            morph_targets: pos_buffer.morph_targets.as_ref().map(TextureView::id),
+        }
+    }
+
+    /// Whether these buffers were made for `pos_buffer` and have room for
+    /// `deformation`
+    fn fits(&self, pos_buffer: &PosBuffer, deformation: &WireframeDeformation) -> bool {
+        self.source == pos_buffer.face_buffer.id()
+            && self.morph_targets == pos_buffer.morph_targets.as_ref().map(TextureView::id)
+            && self.joint_capacity >= deformation.joints.len()
+            && self.morph_weight_capacity >= deformation.morph_weights.len()
+    }
+
+    /// Write the pose of the entity into the buffers
+    fn write(
+        &self,
+        render_queue: &RenderQueue,
+        pos_buffer: &PosBuffer,
+        deformation: &WireframeDeformation,
+    ) {
+        let skinned = pos_buffer.joint_buffers.is_some() && !deformation.joints.is_empty();
+        let morph_count = match pos_buffer.morph_targets {
+            Some(_) => deformation.morph_weights.len(),
+            None => 0,
+        };
+        if !deformation.joints.is_empty() {
+            render_queue.write_buffer(
+                &self.joint_buffer,
+                0,
+                bytemuck::cast_slice(deformation.joints.as_slice()),
+            );
+        }
+        if !deformation.morph_weights.is_empty() {
+            render_queue.write_buffer(
+                &self.morph_weight_buffer,
+                0,
+                bytemuck::cast_slice(deformation.morph_weights.as_slice()),
+            );
+        }
+        render_queue.write_buffer(
+            &self.deform_buffer,
+            0,
+            bytemuck::cast_slice(&[UVec4::new(skinned as u32, morph_count as u32, 0, 0)]),
+        );
+    }
+}
@@ -0,0 +1,68 @@
+// Deforms the positions of a skinned or morphed entity into its model space,
+// where the face compute measures them. Bevy deforms the same vertices in its
+// mesh vertex shader, so the wires follow the animated surface.
+
+// The mesh's vertices, as `f32`s laid out as described by `vertex_layout`
+@group(0) @binding(0) var<storage> vertices: array<f32>;
+// (stride, offset, vertex_count, 0), in `f32`s
+@group(0) @binding(1) var<uniform> vertex_layout: vec4<u32>;
+@group(0) @binding(2) var<storage, read_write> deformed: array<vec4<f32>>;
+// The joints of each vertex and their weights; only read when skinned
+@group(0) @binding(3) var<storage> joint_indices: array<vec4<u32>>;
+@group(0) @binding(4) var<storage> joint_weights: array<vec4<f32>>;
+// Each joint's transform from the bind pose into the entity's model space
+@group(0) @binding(5) var<storage> joints: array<mat4x4<f32>>;
+// The weight of each morph target
+@group(0) @binding(6) var<storage> morph_weights: array<f32>;
+@group(0) @binding(7) var morph_targets: texture_3d<f32>;
+// (skinned, morph target count, 0, 0)
+@group(0) @binding(8) var<uniform> deform: vec4<u32>;
+
+// The layout of bevy's morph target texture, see `bevy_pbr::morph`
+const position_offset: u32 = 0u;
+const total_component_count: u32 = 9u;
+
+fn morph_pixel(vertex: u32, component: u32, weight: u32) -> f32 {
+    let width = u32(textureDimensions(morph_targets).x);
+    let component_index = total_component_count * vertex + component;
+    let coord = vec2<u32>(component_index % width, component_index / width);
+    return textureLoad(morph_targets, vec3(coord, weight), 0).r;
+}
+
+fn morph_position(vertex: u32, weight: u32) -> vec3<f32> {
+    return vec3<f32>(
+        morph_pixel(vertex, position_offset, weight),
+        morph_pixel(vertex, position_offset + 1u, weight),
+        morph_pixel(vertex, position_offset + 2u, weight),
+    );
+}
+
+// Compute shader, one invocation per vertex
+@compute @workgroup_size(#{WORKGROUP_SIZE})
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let vertex = global_id.y * num_workgroups.x * #{WORKGROUP_SIZE}u + global_id.x;
+    if (vertex >= arrayLength(&deformed)) {
+        return;
+    }
+    let i = vertex * vertex_layout.x + vertex_layout.y;
+    var position = vec3<f32>(vertices[i], vertices[i + 1u], vertices[i + 2u]);
+
+    // Morph targets are applied first, as in bevy's vertex shader.
+    for (var w = 0u; w < deform.y; w++) {
+        position += morph_weights[w] * morph_position(vertex, w);
+    }
+
+    if (deform.x != 0u) {
+        let indices = joint_indices[vertex];
+        let weights = joint_weights[vertex];
+        let skin = weights.x * joints[indices.x]
+            + weights.y * joints[indices.y]
+            + weights.z * joints[indices.z]
+            + weights.w * joints[indices.w];
+        position = (skin * vec4<f32>(position, 1.0)).xyz;
+    }
+    deformed[vertex] = vec4<f32>(position, 1.0);
+}
@@ -1,49 +1,77 @@
-// Structured buffer for input vertex data
-struct VertexData {
-    position: vec4<f32>,  // Vertex position with a w-component
-}
-
 // Structured buffer for output data
 struct OutputData {
     face: vec4<f32>,  // triangle lengths and area: (l0, l1, l2, area)
 }
 
-// Uniform for window scale
-// struct WinScale {
-//     scale: vec2<f32>,
-// }
+// The face data is always in model units. Screen-space distances depend on the
+// view, so they are measured per view in the wireframe vertex shader.
 
 // Buffers
-@group(0) @binding(0) var<storage> vertexInput: array<VertexData>;
+// The mesh's vertices, as `f32`s laid out as described by `vertex_layout`
+@group(0) @binding(0) var<storage> vertexInput: array<f32>;
 // @group(0) @binding(1) var<storage, read_write> vertexInput2: array<VertexData>;
 @group(0) @binding(1) var<storage, read_write> outputBuffer: array<OutputData>;
-// @group(0) @binding(2) var<uniform> winScale: WinScale;
+// The mesh's indices; only read when the mesh is INDEXED
+@group(0) @binding(2) var<storage> indices: array<u32>;
+// The stride between vertices and the offset of the position in each vertex,
+// in `f32`s, the number of vertices, and whether the mesh has indices:
+// (stride, offset, vertex_count, indexed)
+@group(0) @binding(3) var<uniform> vertex_layout: vec4<u32>;
+// The triangles to compute: (first, count, 0, 0)
+@group(0) @binding(4) var<uniform> triangle_range: vec4<u32>;
 
-// Compute shader
-@compute @workgroup_size(1)
-fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let tri_index = global_id.x;
-    let index = global_id.x * 3;  // This index maps to a set of vertices (assuming they come in groups of 3)
+fn vertex_count() -> u32 {
+#ifdef INDEXED
+    return arrayLength(&indices);
+#else
+    return vertex_layout.z;
+#endif
+}
+
+fn vertex_at(vertex: u32) -> vec4<f32> {
+    let i = vertex * vertex_layout.x + vertex_layout.y;
+    return vec4<f32>(vertexInput[i], vertexInput[i + 1u], vertexInput[i + 2u], 1.0);
+}
+
+fn vertex_position(index: u32) -> vec4<f32> {
+#ifdef INDEXED
+    return vertex_at(indices[index]);
+#else
+    return vertex_at(index);
+#endif
+}
+
+// Compute shader, one invocation per triangle
+@compute @workgroup_size(#{WORKGROUP_SIZE})
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    // Dispatches too large for one dimension are split into rows of
+    // workgroups.
+    let local_index = global_id.y * num_workgroups.x * #{WORKGROUP_SIZE}u + global_id.x;
+    // The last workgroup runs past the end of the range.
+    if (local_index >= triangle_range.y) {
+        return;
+    }
+    let tri_index = triangle_range.x + local_index;
+    let index = tri_index * 3u;  // This index maps to a set of vertices (assuming they come in groups of 3)
 
     // Ensure we have enough data (assuming input vertices come in groups of 3)
-    if (index + 2u < arrayLength(&vertexInput)) {
-        // let p0 = winScale.scale * (vertexInput[index].position.xy / vertexInput[index].position.w);
-        // let p1 = winScale.scale * (vertexInput[index + 1].position.xy / vertexInput[index + 1].position.w);
-        // let p2 = winScale.scale * (vertexInput[index + 2].position.xy / vertexInput[index + 2].position.w);
+    if (index + 2u < vertex_count()) {
         // let p0 = (vertexInput[index].position.xy / vertexInput[index].position.w);
         // let p1 = (vertexInput[index + 1].position.xy / vertexInput[index + 1].position.w);
         // let p2 = (vertexInput[index + 2].position.xy / vertexInput[index + 2].position.w);
 #ifdef MODEL_DIST
-        let p0 = vertexInput[index].position.xyz;
-        let p1 = vertexInput[index + 1].position.xyz;
-        let p2 = vertexInput[index + 2].position.xyz;
+        let p0 = vertex_position(index).xyz;
+        let p1 = vertex_position(index + 1).xyz;
+        let p2 = vertex_position(index + 2).xyz;
 #else
-        let p0 = vertexInput[index].position.xy;
-        let p1 = vertexInput[index + 1].position.xy;
-        let p2 = vertexInput[index + 2].position.xy;
+        let p0 = vertex_position(index).xy;
+        let p1 = vertex_position(index + 1).xy;
+        let p2 = vertex_position(index + 2).xy;
 #endif
 
-        let resolution = vec2<f32>(800.0, 400.0);
         let v0 = p2 - p1;
         let v1 = p2 - p0;
         let v2 = p1 - p0;
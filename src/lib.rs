@@ -1,6 +1,14 @@
+pub mod backend;
 pub(crate) mod compute;
+pub(crate) mod deform;
+pub mod material2d;
+pub mod material3d;
+pub mod mesh;
+pub mod style;
 pub mod wireframe2d;
+pub mod wireframe3d;
 
-pub(crate) fn pad(v: [f32; 3]) -> [f32; 4] {
-    [v[0], v[1], v[2], 0.0]
+pub(crate) fn color_to_vec4(color: bevy::color::Color) -> bevy::math::Vec4 {
+    let c = color.to_linear();
+    bevy::math::Vec4::new(c.red, c.green, c.blue, c.alpha)
 }
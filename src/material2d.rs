@@ -0,0 +1,212 @@
+use crate::{
+    mesh::{ATTRIBUTE_BARYCENTRIC, ATTRIBUTE_EDGE_COLOR, ATTRIBUTE_WIRE_COLOR},
+    style::{WireframeSpace, WireframeStyle},
+    wireframe2d::WireframeMesh2d,
+};
+use bevy::{
+    app::{App, Last, Plugin},
+    asset::{embedded_asset, Asset, AssetEvent, Assets, Handle},
+    color::Color,
+    ecs::{
+        entity::Entity,
+        event::EventReader,
+        query::{Added, Or, With, Without},
+        system::{Query, Res},
+    },
+    log::warn,
+    math::Vec4,
+    reflect::TypePath,
+    render::{
+        mesh::{Mesh, MeshVertexBufferLayoutRef},
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
+        },
+        texture::GpuImage,
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+/// A [`Material2d`] that draws a mesh as a wireframe
+///
+/// Unlike [`WireframeMesh2d`], it needs no face buffers, so its meshes batch and
+/// share bind groups like any other 2d material. Instead the mesh needs an
+/// [`ATTRIBUTE_BARYCENTRIC`], which
+/// [`with_barycentric`](crate::mesh::with_barycentric) adds, hiding the edges
+/// of the mesh's [`ATTRIBUTE_EDGE_MASK`](crate::mesh::ATTRIBUTE_EDGE_MASK).
+///
+/// Only the fields of the material are drawn. Everything else needs the face
+/// buffers and isn't supported: quad diagonals are only hidden by an edge
+/// mask, there are no crease or outline edges, modes or dashes, and an
+/// [`ATTRIBUTE_WIRE_COLOR`] or [`ATTRIBUTE_EDGE_COLOR`] of the mesh and a
+/// [`WireframeStyle`] of the entity are ignored with a warning. Draw those with
+/// [`WireframeMesh2d`] instead.
+#[derive(Asset, AsBindGroup, TypePath, Clone, Debug)]
+#[uniform(0, WireframeMaterial2dUniform)]
+#[bind_group_data(WireframeMaterial2dKey)]
+pub struct WireframeMaterial2d {
+    /// Color of the wire.
+    pub wire_color: Color,
+    /// Color of the triangle interiors.
+    pub fill_color: Color,
+    /// Width of the wire in [`WireframeMaterial2d::space`] units.
+    pub line_width: f32,
+    /// How quickly the wire fades into the fill.
+    pub sharpness: f32,
+    /// The units of the line width. A material may be drawn by several views
+    /// with different scale factors, so it can't measure logical pixels, and
+    /// [`WireframeSpace::Screen`] widths are drawn as
+    /// [`WireframeSpace::PhysicalScreen`] ones with a warning.
+    pub space: WireframeSpace,
+}
+
+impl Default for WireframeMaterial2d {
+    /// A red wire over a white fill, one model unit wide with a sharpness of
+    /// 2.0, like [`WireframeStyle::default`].
+    fn default() -> Self {
+        Self {
+            wire_color: Color::srgb(1.0, 0.0, 0.0),
+            fill_color: Color::WHITE,
+            line_width: 1.0,
+            sharpness: 2.0,
+            space: WireframeSpace::Model,
+        }
+    }
+}
+
+/// The GPU representation of a [`WireframeMaterial2d`]
+#[derive(Clone, Copy, Debug, ShaderType)]
+pub struct WireframeMaterial2dUniform {
+    pub wire_color: Vec4,
+    pub fill_color: Vec4,
+    pub line_width: f32,
+    pub sharpness: f32,
+}
+
+impl AsBindGroupShaderType<WireframeMaterial2dUniform> for WireframeMaterial2d {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<GpuImage>,
+    ) -> WireframeMaterial2dUniform {
+        WireframeMaterial2dUniform {
+            wire_color: crate::color_to_vec4(self.wire_color),
+            fill_color: crate::color_to_vec4(self.fill_color),
+            line_width: self.line_width,
+            sharpness: self.sharpness,
+        }
+    }
+}
+
+/// Specializes the [`WireframeMaterial2d`] pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WireframeMaterial2dKey {
+    space: WireframeSpace,
+}
+
+impl From<&WireframeMaterial2d> for WireframeMaterial2dKey {
+    fn from(material: &WireframeMaterial2d) -> Self {
+        Self {
+            space: material.space,
+        }
+    }
+}
+
+impl Material2d for WireframeMaterial2d {
+    fn vertex_shader() -> ShaderRef {
+        "embedded://bevy_wireframe/wireframe_material2d.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "embedded://bevy_wireframe/wireframe_material2d.wgsl".into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Pipelines are specialized once for each vertex layout.
+        for attribute in [ATTRIBUTE_WIRE_COLOR, ATTRIBUTE_EDGE_COLOR] {
+            if layout.0.contains(attribute.clone()) {
+                warn!(
+                    "Ignoring the {} of a mesh drawn with WireframeMaterial2d, which has no face \
+                    buffers to color its wires with. Draw it with WireframeMesh2d instead.",
+                    attribute.name
+                );
+            }
+        }
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_BARYCENTRIC.at_shader_location(1),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        if key.bind_group_data.space.is_screen() {
+            descriptor.vertex.shader_defs.push("SCREEN_SPACE".into());
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("SCREEN_SPACE".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A component bundle for entities with a [`Mesh2dHandle`](bevy::sprite::Mesh2dHandle)
+/// and a [`WireframeMaterial2d`]
+pub type WireframeMaterialMesh2dBundle = MaterialMesh2dBundle<WireframeMaterial2d>;
+
+/// Plugin that renders meshes with a [`WireframeMaterial2d`]
+pub struct WireframeMaterial2dPlugin;
+
+impl Plugin for WireframeMaterial2dPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "wireframe_material2d.wgsl");
+        app.add_plugins(Material2dPlugin::<WireframeMaterial2d>::default())
+            .add_systems(Last, (reject_wireframe_styles, reject_screen_space));
+    }
+}
+
+/// Warn about the [`WireframeStyle`] of an entity drawn with a
+/// [`WireframeMaterial2d`], which only draws the fields of the material
+#[allow(clippy::type_complexity)]
+fn reject_wireframe_styles(
+    query: Query<
+        Entity,
+        (
+            With<Handle<WireframeMaterial2d>>,
+            With<WireframeStyle>,
+            Without<WireframeMesh2d>,
+            Or<(Added<WireframeStyle>, Added<Handle<WireframeMaterial2d>>)>,
+        ),
+    >,
+) {
+    for entity in &query {
+        warn!(
+            "Ignoring the WireframeStyle of entity {entity:?}, which is drawn with a \
+            WireframeMaterial2d. Mark it with WireframeMesh2d to draw the style."
+        );
+    }
+}
+
+/// Warn about a [`WireframeMaterial2d`] with [`WireframeSpace::Screen`]
+/// widths, which are drawn in physical pixels
+fn reject_screen_space(
+    mut events: EventReader<AssetEvent<WireframeMaterial2d>>,
+    materials: Res<Assets<WireframeMaterial2d>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if materials
+            .get(*id)
+            .is_some_and(|material| material.space == WireframeSpace::Screen)
+        {
+            warn!(
+                "Drawing the WireframeSpace::Screen widths of WireframeMaterial2d {id:?} in \
+                physical pixels, as materials can't scale them by each view's scale factor. \
+                Use WireframeSpace::PhysicalScreen to say so."
+            );
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{mesh::ATTRIBUTE_BARYCENTRIC, style::WireframeSpace};
+use bevy::{
+    app::{App, Last, Plugin},
+    asset::{embedded_asset, Asset, AssetEvent, Assets},
+    color::Color,
+    ecs::{event::EventReader, system::Res},
+    log::warn,
+    math::Vec4,
+    pbr::{
+        ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
+        MaterialPlugin, StandardMaterial,
+    },
+    reflect::TypePath,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
+        },
+        texture::GpuImage,
+    },
+};
+
+/// A [`StandardMaterial`] with a wireframe drawn over its lit surface
+pub type WireframeStandardMaterial = ExtendedMaterial<StandardMaterial, WireframeExtension>;
+
+/// A [`MaterialExtension`] that mixes a wire over the PBR output of its base
+/// material, with the falloff of [`WireframeStyle`](crate::style::WireframeStyle)
+///
+/// A material's bind group is shared by every mesh using it, so the per-mesh
+/// face buffers of the [`FacePlugin`](crate::compute::FacePlugin) can't be
+/// bound. Like [`WireframeMaterial2d`](crate::material2d::WireframeMaterial2d),
+/// the mesh needs an [`ATTRIBUTE_BARYCENTRIC`] instead, which
+/// [`with_barycentric`](crate::mesh::with_barycentric) adds, and the distance
+/// to each edge is measured from how those coordinates change across each
+/// triangle. Meshes without it aren't drawn.
+///
+/// Skinned and morphed meshes are posed as usual, and the coordinates follow
+/// their posed triangles. Model space widths are measured on the morphed mesh
+/// before it is skinned, in the mesh's units.
+#[derive(Asset, AsBindGroup, TypePath, Clone, Debug)]
+#[uniform(100, WireframeExtensionUniform)]
+#[bind_group_data(WireframeExtensionKey)]
+pub struct WireframeExtension {
+    /// Color of the wire. Its alpha blends the wire over the lit surface.
+    pub wire_color: Color,
+    /// Width of the wire in [`WireframeExtension::space`] units.
+    pub line_width: f32,
+    /// How quickly the wire fades into the surface.
+    pub sharpness: f32,
+    /// The units of the line width. A material may be drawn by several views
+    /// with different scale factors, so it can't measure logical pixels, and
+    /// [`WireframeSpace::Screen`] widths are drawn as
+    /// [`WireframeSpace::PhysicalScreen`] ones with a warning.
+    pub space: WireframeSpace,
+}
+
+impl Default for WireframeExtension {
+    fn default() -> Self {
+        Self {
+            wire_color: Color::BLACK,
+            line_width: 1.0,
+            sharpness: 2.0,
+            space: WireframeSpace::PhysicalScreen,
+        }
+    }
+}
+
+/// The GPU representation of a [`WireframeExtension`]
+#[derive(Clone, Copy, Debug, ShaderType)]
+pub struct WireframeExtensionUniform {
+    pub wire_color: Vec4,
+    pub line_width: f32,
+    pub sharpness: f32,
+}
+
+impl AsBindGroupShaderType<WireframeExtensionUniform> for WireframeExtension {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<GpuImage>,
+    ) -> WireframeExtensionUniform {
+        WireframeExtensionUniform {
+            wire_color: crate::color_to_vec4(self.wire_color),
+            line_width: self.line_width,
+            sharpness: self.sharpness,
+        }
+    }
+}
+
+/// Specializes the [`WireframeExtension`] pipelines
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WireframeExtensionKey {
+    space: WireframeSpace,
+}
+
+impl From<&WireframeExtension> for WireframeExtensionKey {
+    fn from(extension: &WireframeExtension) -> Self {
+        Self {
+            space: extension.space,
+        }
+    }
+}
+
+impl MaterialExtension for WireframeExtension {
+    fn vertex_shader() -> ShaderRef {
+        "embedded://bevy_wireframe/wireframe_extension.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "embedded://bevy_wireframe/wireframe_extension.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Add the barycentric coordinates to the base material's vertex
+        // buffer, past the locations the mesh and prepass shaders use.
+        let barycentric = layout
+            .0
+            .get_layout(&[ATTRIBUTE_BARYCENTRIC.at_shader_location(8)])?;
+        if let Some(buffer) = descriptor.vertex.buffers.first_mut() {
+            buffer.attributes.extend(barycentric.attributes);
+        }
+        if key.bind_group_data.space.is_screen() {
+            descriptor.vertex.shader_defs.push("SCREEN_SPACE".into());
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("SCREEN_SPACE".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plugin that renders meshes with a [`WireframeStandardMaterial`]
+pub struct WireframeExtensionPlugin;
+
+impl Plugin for WireframeExtensionPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "wireframe_extension.wgsl");
+        app.add_plugins(MaterialPlugin::<WireframeStandardMaterial>::default())
+            .add_systems(Last, reject_screen_space);
+    }
+}
+
+/// Warn about a [`WireframeExtension`] with [`WireframeSpace::Screen`] widths,
+/// which are drawn in physical pixels
+fn reject_screen_space(
+    mut events: EventReader<AssetEvent<WireframeStandardMaterial>>,
+    materials: Res<Assets<WireframeStandardMaterial>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if materials
+            .get(*id)
+            .is_some_and(|material| material.extension.space == WireframeSpace::Screen)
+        {
+            warn!(
+                "Drawing the WireframeSpace::Screen widths of WireframeStandardMaterial {id:?} \
+                in physical pixels, as materials can't scale them by each view's scale factor. \
+                Use WireframeSpace::PhysicalScreen to say so."
+            );
+        }
+    }
+}
@@ -0,0 +1,929 @@
+use bevy::{
+    log::warn,
+    math::Vec3,
+    render::{
+        mesh::{Indices, Mesh, MeshVertexAttribute, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+        render_resource::{PrimitiveTopology, VertexFormat},
+    },
+    utils::HashMap,
+};
+
+/// Which edges of a triangle to hide
+///
+/// The mask of a triangle is read from its first vertex. Bit `i` hides the edge
+/// opposite the triangle's `i`th vertex. A vertex shared by several triangles
+/// can't hold a mask for each, so the mesh must be an unindexed triangle list,
+/// where each triangle has its own vertices, see [`Mesh::duplicate_vertices`].
+/// Indexed meshes, and meshes without this attribute, hide the diagonals of
+/// their quads instead.
+///
+/// The format of this attribute is [`VertexFormat::Uint32`].
+pub const ATTRIBUTE_EDGE_MASK: MeshVertexAttribute =
+    MeshVertexAttribute::new("Wireframe_EdgeMask", 1_846_107_453, VertexFormat::Uint32);
+
+/// The linear color of the wire at each vertex, blended along each edge
+/// between its two vertices. It replaces [`WireframeStyle::wire_color`].
+///
+/// The format of this attribute is [`VertexFormat::Float32x4`].
+///
+/// [`WireframeStyle::wire_color`]: crate::style::WireframeStyle::wire_color
+pub const ATTRIBUTE_WIRE_COLOR: MeshVertexAttribute =
+    MeshVertexAttribute::new("Wireframe_WireColor", 1_846_107_454, VertexFormat::Float32x4);
+
+/// The linear color of the edge opposite each vertex of a triangle
+///
+/// Like [`ATTRIBUTE_EDGE_MASK`], vertex `i` of a triangle colors the edge
+/// opposite it. An edge shared by several triangles, welded by the positions of
+/// their vertices, is colored by the first of them in the mesh, so it has the
+/// same color whichever triangle draws it. It takes precedence over
+/// [`ATTRIBUTE_WIRE_COLOR`].
+///
+/// The format of this attribute is [`VertexFormat::Float32x4`].
+pub const ATTRIBUTE_EDGE_COLOR: MeshVertexAttribute =
+    MeshVertexAttribute::new("Wireframe_EdgeColor", 1_846_107_455, VertexFormat::Float32x4);
+
+/// The barycentric coordinates of each vertex within its triangle
+///
+/// Used by [`WireframeMaterial2d`](crate::material2d::WireframeMaterial2d),
+/// which has no face buffers and measures the distance to each edge from how
+/// these coordinates change across a triangle, and by
+/// [`WireframeExtension`](crate::material3d::WireframeExtension) for the same
+/// reason. See [`with_barycentric`].
+///
+/// The format of this attribute is [`VertexFormat::Float32x3`].
+pub const ATTRIBUTE_BARYCENTRIC: MeshVertexAttribute =
+    MeshVertexAttribute::new("Wireframe_Barycentric", 1_846_107_456, VertexFormat::Float32x3);
+
+/// The lengths of the edges of each vertex's triangle, edge `i` opposite
+/// vertex `i`, and twice its area
+///
+/// Written with [`ATTRIBUTE_CREASE_ANGLES`] by
+/// [`WireframeBackend::VertexAttributes`], which reads the face data of a
+/// triangle from its vertices instead of a face buffer.
+///
+/// The format of this attribute is [`VertexFormat::Float32x4`].
+///
+/// [`WireframeBackend::VertexAttributes`]: crate::backend::WireframeBackend::VertexAttributes
+pub const ATTRIBUTE_FACE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Wireframe_Face", 1_846_107_457, VertexFormat::Float32x4);
+
+/// The angle in radians between each vertex's triangle and its neighbor
+/// across each edge, like [`ATTRIBUTE_FACE`]
+///
+/// The format of this attribute is [`VertexFormat::Float32x3`].
+pub const ATTRIBUTE_CREASE_ANGLES: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Wireframe_CreaseAngles",
+    1_846_107_458,
+    VertexFormat::Float32x3,
+);
+
+/// The position of the other end of the line segment whose quad a vertex is a
+/// corner of, in the copies of line meshes drawn by
+/// [`WireframeBackend::VertexAttributes`]
+///
+/// The format of this attribute is [`VertexFormat::Float32x3`].
+///
+/// [`WireframeBackend::VertexAttributes`]: crate::backend::WireframeBackend::VertexAttributes
+pub const ATTRIBUTE_SEGMENT_END: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Wireframe_SegmentEnd",
+    1_846_107_459,
+    VertexFormat::Float32x3,
+);
+
+/// Give a triangle mesh an [`ATTRIBUTE_BARYCENTRIC`]
+///
+/// Indexed meshes are unindexed first, since a vertex's barycentric
+/// coordinates depend on the triangle it belongs to, and strips are made into
+/// lists, see [`triangle_list`]. Edges hidden by the mesh's
+/// [`ATTRIBUTE_EDGE_MASK`] keep a coordinate of one, so they are never drawn.
+/// Line and point meshes are returned as they are.
+pub fn with_barycentric(mesh: Mesh) -> Mesh {
+    let mut mesh = match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => mesh,
+        PrimitiveTopology::TriangleStrip => triangle_list(&mesh),
+        topology => {
+            warn!("Not adding barycentric coordinates to a {topology:?} mesh without triangles");
+            return mesh;
+        }
+    };
+    let masks = edge_mask_attribute(&mesh).map(<[u32]>::to_vec);
+    mesh.duplicate_vertices();
+    let barycentric: Vec<[f32; 3]> = (0..mesh.count_vertices())
+        .map(|v| {
+            // Triangle `t` is now made of vertices `3 * t` to `3 * t + 2`.
+            let mut bary = [0.0; 3];
+            bary[v % 3] = 1.0;
+            if let Some(masks) = &masks {
+                let mask = masks[v - v % 3];
+                for (i, b) in bary.iter_mut().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        *b = 1.0;
+                    }
+                }
+            }
+            bary
+        })
+        .collect();
+    mesh.insert_attribute(ATTRIBUTE_BARYCENTRIC, barycentric);
+    mesh
+}
+
+/// The vertex attributes [`triangle_list`] copies: bevy's own and the wire
+/// colors
+const TRIANGLE_LIST_ATTRIBUTES: [MeshVertexAttribute; 10] = [
+    Mesh::ATTRIBUTE_POSITION,
+    Mesh::ATTRIBUTE_NORMAL,
+    Mesh::ATTRIBUTE_UV_0,
+    Mesh::ATTRIBUTE_UV_1,
+    Mesh::ATTRIBUTE_TANGENT,
+    Mesh::ATTRIBUTE_COLOR,
+    Mesh::ATTRIBUTE_JOINT_WEIGHT,
+    Mesh::ATTRIBUTE_JOINT_INDEX,
+    ATTRIBUTE_WIRE_COLOR,
+    ATTRIBUTE_EDGE_COLOR,
+];
+
+/// A triangle list of the triangles of a strip mesh, indexed by
+/// [`triangle_list_indices`] so restart indices split it
+///
+/// A mesh's topology can't be changed, so this is a new mesh, and only the
+/// attributes it knows how to copy, [`TRIANGLE_LIST_ATTRIBUTES`], are kept.
+/// Other attributes and morph targets are dropped with a warning.
+pub(crate) fn triangle_list(mesh: &Mesh) -> Mesh {
+    let mut list = Mesh::new(PrimitiveTopology::TriangleList, mesh.asset_usage);
+    for attribute in TRIANGLE_LIST_ATTRIBUTES {
+        if let Some(values) = mesh.attribute(attribute.id) {
+            list.insert_attribute(attribute, values.clone());
+        }
+    }
+    if list.attributes().count() < mesh.attributes().count() || mesh.has_morph_targets() {
+        warn!(
+            "Dropping the custom vertex attributes and morph targets of a {:?} mesh \
+            made into a triangle list",
+            mesh.primitive_topology(),
+        );
+    }
+    if let Some(indices) = triangle_list_indices(mesh) {
+        list.insert_indices(Indices::U32(indices));
+    }
+    list
+}
+
+/// Bake the face data of a triangle list mesh into its vertices
+///
+/// The mesh is unindexed, so each triangle has its own vertices, which are
+/// given an [`ATTRIBUTE_BARYCENTRIC`] without hidden edges, an
+/// [`ATTRIBUTE_FACE`], an [`ATTRIBUTE_CREASE_ANGLES`] and the
+/// [`ATTRIBUTE_EDGE_MASK`] of their triangle.
+pub(crate) fn insert_face_attributes(mesh: &mut Mesh) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let triangles = triangles(mesh);
+    let adjacency = Adjacency::new(positions, &triangles);
+    let masks = edge_masks(mesh, positions, &triangles, &adjacency);
+    let creases = crease_angles(positions, &triangles, &adjacency);
+    let faces = triangle_faces(positions, &triangles);
+
+    // Triangle `t` is now made of vertices `3 * t` to `3 * t + 2`.
+    mesh.duplicate_vertices();
+    let barycentric: Vec<[f32; 3]> = (0..3 * triangles.len())
+        .map(|v| {
+            let mut bary = [0.0; 3];
+            bary[v % 3] = 1.0;
+            bary
+        })
+        .collect();
+    mesh.insert_attribute(ATTRIBUTE_BARYCENTRIC, barycentric);
+    mesh.insert_attribute(ATTRIBUTE_FACE, per_vertex(&faces));
+    let creases: Vec<[f32; 3]> = creases.iter().map(|c| [c[0], c[1], c[2]]).collect();
+    mesh.insert_attribute(ATTRIBUTE_CREASE_ANGLES, per_vertex(&creases));
+    mesh.insert_attribute(ATTRIBUTE_EDGE_MASK, per_vertex(&masks));
+}
+
+/// The lengths of the edges of each triangle, edge `i` opposite vertex `i`,
+/// and twice its area, as measured by `face_compute.wgsl`
+fn triangle_faces(positions: &[[f32; 3]], triangles: &[[u32; 3]]) -> Vec<[f32; 4]> {
+    triangles
+        .iter()
+        .map(|t| {
+            let [p0, p1, p2] = corners(positions, t);
+            let area = (p2 - p0).cross(p1 - p0).length();
+            [p2.distance(p1), p2.distance(p0), p1.distance(p0), area]
+        })
+        .collect()
+}
+
+/// Repeat the value of each triangle at its three vertices
+fn per_vertex<T: Copy>(values: &[T]) -> Vec<T> {
+    values.iter().flat_map(|value| [*value; 3]).collect()
+}
+
+/// The indices of a triangle mesh as a triangle list, or `None` when its
+/// vertices already are one. Lines and points have no triangles.
+pub(crate) fn triangle_list_indices(mesh: &Mesh) -> Option<Vec<u32>> {
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => mesh
+            .indices()
+            .map(|indices| indices.iter().map(|i| i as u32).collect()),
+        PrimitiveTopology::TriangleStrip => {
+            let (strip, restart) = strip_indices(mesh);
+            // A restart index starts a new strip, and every other triangle of
+            // a strip is wound the other way.
+            Some(
+                strip
+                    .split(|i| Some(*i) == restart)
+                    .flat_map(|strip| {
+                        strip.windows(3).enumerate().flat_map(|(i, w)| {
+                            if i % 2 == 0 {
+                                [w[0], w[1], w[2]]
+                            } else {
+                                [w[1], w[0], w[2]]
+                            }
+                        })
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The vertex indices of a strip mesh in order, and the restart index that
+/// ends a strip early, if it is indexed
+fn strip_indices(mesh: &Mesh) -> (Vec<u32>, Option<u32>) {
+    match mesh.indices() {
+        Some(Indices::U16(indices)) => (
+            indices.iter().map(|i| u32::from(*i)).collect(),
+            Some(u16::MAX.into()),
+        ),
+        Some(Indices::U32(indices)) => (indices.clone(), Some(u32::MAX)),
+        None => ((0..mesh.count_vertices() as u32).collect(), None),
+    }
+}
+
+/// The indices of a line mesh as a line list, or `None` when its vertices
+/// already are one. Triangles and points have no line segments.
+pub(crate) fn line_list_indices(mesh: &Mesh) -> Option<Vec<u32>> {
+    match mesh.primitive_topology() {
+        PrimitiveTopology::LineList => mesh
+            .indices()
+            .map(|indices| indices.iter().map(|i| i as u32).collect()),
+        PrimitiveTopology::LineStrip => {
+            let (strip, restart) = strip_indices(mesh);
+            Some(
+                strip
+                    .split(|i| Some(*i) == restart)
+                    .flat_map(|strip| strip.windows(2).flatten().copied())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The vertex indices of each line segment of a mesh
+pub(crate) fn line_segments(mesh: &Mesh) -> Vec<[u32; 2]> {
+    match line_list_indices(mesh) {
+        Some(indices) => indices.chunks_exact(2).map(|s| [s[0], s[1]]).collect(),
+        None if mesh.primitive_topology() == PrimitiveTopology::LineList => {
+            (0..mesh.count_vertices() as u32 / 2)
+                .map(|s| [2 * s, 2 * s + 1])
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// The end of its segment at each corner of a line segment's quad, for the
+/// two triangles `0, 1, 2` and `3, 4, 5` across it
+pub(crate) const QUAD_CORNER_ENDS: [usize; 6] = [0, 1, 1, 0, 1, 0];
+
+/// A copy of a line mesh with a quad for each segment, as a triangle list of
+/// six vertices per segment with [`ATTRIBUTE_SEGMENT_END`], for the shader to
+/// widen each segment into an antialiased wire
+///
+/// Only the positions and [`ATTRIBUTE_WIRE_COLOR`] are copied.
+pub(crate) fn line_quads(mesh: &Mesh) -> Option<Mesh> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let segments = line_segments(mesh);
+    let corners = |end: fn(usize) -> usize| -> Vec<u32> {
+        segments
+            .iter()
+            .flat_map(|segment| QUAD_CORNER_ENDS.map(|e| segment[end(e)]))
+            .collect()
+    };
+    let (ends, other_ends) = (corners(|e| e), corners(|e| 1 - e));
+
+    let mut quads = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    let at = |values: &[[f32; 3]], vertices: &[u32]| -> Vec<[f32; 3]> {
+        vertices.iter().map(|v| values[*v as usize]).collect()
+    };
+    quads.insert_attribute(Mesh::ATTRIBUTE_POSITION, at(positions, &ends));
+    quads.insert_attribute(ATTRIBUTE_SEGMENT_END, at(positions, &other_ends));
+    if let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(ATTRIBUTE_WIRE_COLOR) {
+        let colors: Vec<[f32; 4]> = ends.iter().map(|v| colors[*v as usize]).collect();
+        quads.insert_attribute(ATTRIBUTE_WIRE_COLOR, colors);
+    }
+    Some(quads)
+}
+
+/// The vertex indices of each triangle of a mesh
+pub(crate) fn triangles(mesh: &Mesh) -> Vec<[u32; 3]> {
+    match triangle_list_indices(mesh) {
+        Some(indices) => indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect(),
+        None if mesh.primitive_topology() == PrimitiveTopology::TriangleList => {
+            (0..mesh.count_vertices() as u32 / 3)
+                .map(|t| [3 * t, 3 * t + 1, 3 * t + 2])
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// The edges shared between triangles
+///
+/// Vertices are welded by position, so meshes made with
+/// [`Mesh::duplicate_vertices`] or split normals still have neighbors.
+pub(crate) struct Adjacency {
+    /// The triangle across each edge of each triangle. Edge `i` is opposite
+    /// vertex `i`.
+    pub neighbors: Vec<[Option<u32>; 3]>,
+    /// The first edge welded to each edge of each triangle, as `3 * t + i` for
+    /// edge `i` of triangle `t`, which is the edge itself if no earlier
+    /// triangle shares it
+    pub first_edges: Vec<[u32; 3]>,
+    /// The welded vertex of each vertex
+    weld: Vec<u32>,
+    /// The welded vertex at each position, by the bits of its coordinates.
+    /// Positions that vertices moved away from are kept until
+    /// [`Adjacency::reweld`] starts over.
+    welded: HashMap<[u32; 3], u32>,
+    /// The triangle edges along each welded edge, in triangle order
+    edges: HashMap<(u32, u32), Vec<(u32, usize)>>,
+}
+
+impl Adjacency {
+    pub fn new(positions: &[[f32; 3]], triangles: &[[u32; 3]]) -> Self {
+        let mut adjacency = Adjacency {
+            neighbors: vec![[None; 3]; triangles.len()],
+            first_edges: vec![[0; 3]; triangles.len()],
+            weld: Vec::with_capacity(positions.len()),
+            welded: HashMap::default(),
+            edges: HashMap::default(),
+        };
+        for position in positions {
+            let weld = adjacency.weld_position(position);
+            adjacency.weld.push(weld);
+        }
+        for (t, triangle) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let edge = adjacency.edge(triangle, i);
+                adjacency.edges.entry(edge).or_default().push((t as u32, i));
+            }
+        }
+        let edges: Vec<(u32, u32)> = adjacency.edges.keys().copied().collect();
+        for edge in edges {
+            adjacency.link(edge);
+        }
+        adjacency
+    }
+
+    /// The welded vertex at a position, which is new if no vertex was there
+    fn weld_position(&mut self, position: &[f32; 3]) -> u32 {
+        let next = self.welded.len() as u32;
+        *self
+            .welded
+            .entry(position.map(f32::to_bits))
+            .or_insert(next)
+    }
+
+    /// Whether vertex `v` is still welded at `position`, so that the positions
+    /// of a modified mesh can be compared without keeping a copy of them
+    pub fn is_welded_at(&self, v: usize, position: &[f32; 3]) -> bool {
+        self.welded.get(&position.map(f32::to_bits)) == self.weld.get(v)
+    }
+
+    /// The welded vertices at the ends of edge `i` of a triangle, in order
+    fn edge(&self, triangle: &[u32; 3], i: usize) -> (u32, u32) {
+        let a = self.weld[triangle[(i + 1) % 3] as usize];
+        let b = self.weld[triangle[(i + 2) % 3] as usize];
+        (a.min(b), a.max(b))
+    }
+
+    /// Set the neighbors and first edges of the triangles along a welded edge
+    fn link(&mut self, edge: (u32, u32)) {
+        let Some(sharing) = self.edges.get(&edge) else {
+            return;
+        };
+        // Triangles are kept in order, so the first one is the lowest.
+        let (t, i) = sharing[0];
+        for &(t1, i1) in sharing {
+            self.first_edges[t1 as usize][i1] = 3 * t + i as u32;
+            self.neighbors[t1 as usize][i1] = None;
+        }
+        // Edges shared by more than two triangles are left as boundaries.
+        if let [(t0, i0), (t1, i1)] = sharing[..] {
+            self.neighbors[t0 as usize][i0] = Some(t1);
+            self.neighbors[t1 as usize][i1] = Some(t0);
+        }
+    }
+
+    /// Weld the vertices of the `moved` triangles again at their `positions`,
+    /// and return every triangle whose neighbors or first edges may have
+    /// changed, sorted
+    ///
+    /// Every triangle with a vertex that moved must be in `moved`. Once more
+    /// positions have been welded than there are vertices, the adjacency is
+    /// built again from scratch, and all triangles are returned.
+    pub fn reweld(
+        &mut self,
+        positions: &[[f32; 3]],
+        triangles: &[[u32; 3]],
+        moved: &[u32],
+    ) -> Vec<u32> {
+        if self.welded.len() + 3 * moved.len() > 2 * positions.len().max(1) {
+            *self = Adjacency::new(positions, triangles);
+            return (0..triangles.len() as u32).collect();
+        }
+        let mut touched = Vec::with_capacity(6 * moved.len());
+        for &t in moved {
+            for i in 0..3 {
+                let edge = self.edge(&triangles[t as usize], i);
+                if let Some(sharing) = self.edges.get_mut(&edge) {
+                    sharing.retain(|shared| *shared != (t, i));
+                    if sharing.is_empty() {
+                        self.edges.remove(&edge);
+                    }
+                }
+                touched.push(edge);
+            }
+        }
+        for &t in moved {
+            for v in triangles[t as usize] {
+                let weld = self.weld_position(&positions[v as usize]);
+                self.weld[v as usize] = weld;
+            }
+        }
+        for &t in moved {
+            for i in 0..3 {
+                let edge = self.edge(&triangles[t as usize], i);
+                let sharing = self.edges.entry(edge).or_default();
+                let at = sharing.partition_point(|shared| *shared < (t, i));
+                sharing.insert(at, (t, i));
+                touched.push(edge);
+            }
+        }
+        touched.sort_unstable();
+        touched.dedup();
+
+        let mut linked = Vec::new();
+        for edge in touched {
+            self.link(edge);
+            if let Some(sharing) = self.edges.get(&edge) {
+                linked.extend(sharing.iter().map(|(t, _)| *t));
+            }
+        }
+        linked.sort_unstable();
+        linked.dedup();
+        linked
+    }
+
+    /// The neighbors of a triangle padded to a `vec4`, with [`NO_NEIGHBOR`]
+    /// across boundary edges
+    pub fn neighbor_index(&self, t: usize) -> [u32; 4] {
+        let [a, b, c] = self.neighbors[t].map(|n| n.unwrap_or(NO_NEIGHBOR));
+        [a, b, c, NO_NEIGHBOR]
+    }
+}
+
+/// The neighbor across a boundary edge, an edge used by only one triangle
+pub(crate) const NO_NEIGHBOR: u32 = u32::MAX;
+
+fn corners(positions: &[[f32; 3]], triangle: &[u32; 3]) -> [Vec3; 3] {
+    triangle.map(|v| Vec3::from(positions[v as usize]))
+}
+
+fn normal(corners: &[Vec3; 3]) -> Vec3 {
+    (corners[1] - corners[0])
+        .cross(corners[2] - corners[0])
+        .normalize_or_zero()
+}
+
+/// The edge of a triangle that is strictly longer than the other two
+fn longest_edge(corners: &[Vec3; 3]) -> Option<usize> {
+    let lengths: [f32; 3] =
+        std::array::from_fn(|i| corners[(i + 1) % 3].distance(corners[(i + 2) % 3]));
+    let i = (0..3).max_by(|a, b| lengths[*a].total_cmp(&lengths[*b]))?;
+    (0..3)
+        .filter(|j| *j != i)
+        .all(|j| lengths[i] > lengths[j] * (1.0 + 1e-4))
+        .then_some(i)
+}
+
+/// How far in radians each corner of a quad may be from a right angle for the
+/// edge between its triangles to be taken as a diagonal
+const QUAD_ANGLE_TOLERANCE: f32 = 0.4;
+
+/// Whether the quad of two triangles sharing the edge from `a` to `b`, with
+/// `p` and `q` their other corners, is close enough to a rectangle for the
+/// edge to be a diagonal
+///
+/// The spokes of a fan, like those of circles and polygons, can also be the
+/// longest edge of both of their triangles, but make thin or uneven quads.
+fn is_rectangular(a: Vec3, b: Vec3, p: Vec3, q: Vec3) -> bool {
+    let right_angle = |corner: Vec3, u: Vec3, v: Vec3| {
+        ((u - corner).angle_between(v - corner) - std::f32::consts::FRAC_PI_2).abs()
+            < QUAD_ANGLE_TOLERANCE
+    };
+    right_angle(p, a, b) && right_angle(q, a, b) && right_angle(a, p, q) && right_angle(b, p, q)
+}
+
+/// The mask of triangle `t` that hides the diagonal of its quad, if it is
+/// half of one: the edge it shares with a coplanar triangle that is the
+/// longest edge of both, and makes a nearly rectangular quad
+fn quad_diagonal_mask(
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+    t: usize,
+) -> u32 {
+    let corners_t = corners(positions, &triangles[t]);
+    let Some(i) = longest_edge(&corners_t) else {
+        return 0;
+    };
+    let Some(n) = adjacency.neighbors[t][i] else {
+        return 0;
+    };
+    let n = n as usize;
+    let corners_n = corners(positions, &triangles[n]);
+    let Some(j) = longest_edge(&corners_n).filter(|j| adjacency.neighbors[n][*j] == Some(t as u32))
+    else {
+        return 0;
+    };
+    let coplanar = normal(&corners_t).dot(normal(&corners_n)) > 1.0 - 1e-4;
+    let [a, b] = [corners_t[(i + 1) % 3], corners_t[(i + 2) % 3]];
+    if coplanar && is_rectangular(a, b, corners_t[i], corners_n[j]) {
+        1 << i
+    } else {
+        0
+    }
+}
+
+/// Mask the diagonals of quads, see [`quad_diagonal_mask`]
+pub(crate) fn quad_diagonal_masks(
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+) -> Vec<u32> {
+    (0..triangles.len())
+        .map(|t| quad_diagonal_mask(positions, triangles, adjacency, t))
+        .collect()
+}
+
+/// The [`ATTRIBUTE_EDGE_MASK`] of a mesh, if it has one and is an unindexed
+/// triangle list that it can be read from
+pub(crate) fn edge_mask_attribute(mesh: &Mesh) -> Option<&[u32]> {
+    let Some(VertexAttributeValues::Uint32(masks)) = mesh.attribute(ATTRIBUTE_EDGE_MASK) else {
+        return None;
+    };
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList || mesh.indices().is_some() {
+        warn!(
+            "Ignoring the ATTRIBUTE_EDGE_MASK of a {:?} mesh (indexed: {}), as only \
+            unindexed triangle lists have a vertex for each triangle",
+            mesh.primitive_topology(),
+            mesh.indices().is_some(),
+        );
+        return None;
+    }
+    Some(masks)
+}
+
+/// The edge mask of triangle `t`, from the mesh's [`edge_mask_attribute`] if
+/// it has one or else its quad diagonal
+pub(crate) fn edge_mask(
+    masks: Option<&[u32]>,
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+    t: usize,
+) -> u32 {
+    match masks {
+        Some(masks) => masks[triangles[t][0] as usize],
+        None => quad_diagonal_mask(positions, triangles, adjacency, t),
+    }
+}
+
+/// The edge mask of each triangle, see [`edge_mask`]
+pub(crate) fn edge_masks(
+    mesh: &Mesh,
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+) -> Vec<u32> {
+    let masks = edge_mask_attribute(mesh);
+    (0..triangles.len())
+        .map(|t| edge_mask(masks, positions, triangles, adjacency, t))
+        .collect()
+}
+
+/// The [`ATTRIBUTE_EDGE_COLOR`] of each edge of triangle `t`, from the
+/// attribute's `colors`
+///
+/// Each edge takes the color of the first edge welded to it.
+pub(crate) fn triangle_edge_colors(
+    colors: &[[f32; 4]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+    t: usize,
+) -> [[f32; 4]; 3] {
+    adjacency.first_edges[t].map(|edge| {
+        let (t, i) = (edge as usize / 3, edge as usize % 3);
+        colors[triangles[t][i] as usize]
+    })
+}
+
+/// The crease angle a boundary edge is given, larger than any dihedral angle
+pub(crate) const BOUNDARY_CREASE: f32 = 4.0;
+
+/// The angle in radians between the normals of triangle `t` and its neighbor
+/// across each edge, padded to a `vec4`. Boundary edges get [`BOUNDARY_CREASE`].
+pub(crate) fn triangle_creases(
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+    t: usize,
+) -> [f32; 4] {
+    let normal_of = |t: usize| normal(&corners(positions, &triangles[t]));
+    let normal_t = normal_of(t);
+    let crease = |i: usize| match adjacency.neighbors[t][i] {
+        Some(n) => normal_t.angle_between(normal_of(n as usize)),
+        None => BOUNDARY_CREASE,
+    };
+    [crease(0), crease(1), crease(2), 0.0]
+}
+
+/// The crease angles of each triangle, see [`triangle_creases`]
+pub(crate) fn crease_angles(
+    positions: &[[f32; 3]],
+    triangles: &[[u32; 3]],
+    adjacency: &Adjacency,
+) -> Vec<[f32; 4]> {
+    (0..triangles.len())
+        .map(|t| triangle_creases(positions, triangles, adjacency, t))
+        .collect()
+}
+
+/// The wireframe data of a [`Mesh`], made on the CPU without a renderer
+///
+/// These are the same values the wireframe plugins compute for each triangle,
+/// so tools can bake them into assets and tests can check them.
+pub trait WireframeMeshExt {
+    /// The mesh with an [`ATTRIBUTE_BARYCENTRIC`], see [`with_barycentric`]
+    fn with_barycentric(self) -> Self;
+
+    /// The lengths of the edges of each triangle, edge `i` opposite vertex
+    /// `i`, and twice its area, as the face compute shader measures them
+    ///
+    /// Meshes without `Float32x3` positions or triangles have no faces.
+    fn compute_face_data(&self) -> Vec<[f32; 4]>;
+
+    /// The edge mask of each triangle that hides the diagonals of the quads,
+    /// the edges between two coplanar triangles that are the longest edge of
+    /// both and whose corners are all close to right angles
+    ///
+    /// Any [`ATTRIBUTE_EDGE_MASK`] of the mesh is ignored.
+    fn edge_mask_for_quads(&self) -> Vec<u32>;
+}
+
+impl WireframeMeshExt for Mesh {
+    fn with_barycentric(self) -> Self {
+        with_barycentric(self)
+    }
+
+    fn compute_face_data(&self) -> Vec<[f32; 4]> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            self.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return Vec::new();
+        };
+        triangle_faces(positions, &triangles(self))
+    }
+
+    fn edge_mask_for_quads(&self) -> Vec<u32> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            self.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return Vec::new();
+        };
+        let triangles = triangles(self);
+        let adjacency = Adjacency::new(positions, &triangles);
+        quad_diagonal_masks(positions, &triangles, &adjacency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::primitives::{Circle, Cuboid, Rectangle};
+    use std::f32::consts::{FRAC_PI_2, SQRT_2};
+
+    fn mesh(topology: PrimitiveTopology, positions: Vec<[f32; 3]>) -> Mesh {
+        Mesh::new(topology, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    }
+
+    fn assert_near(a: [f32; 4], b: [f32; 4]) {
+        assert!(
+            a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-5),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn right_triangle_face_data() {
+        let triangle = mesh(
+            PrimitiveTopology::TriangleList,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        );
+        let faces = triangle.compute_face_data();
+        assert_eq!(faces.len(), 1);
+        assert_near(faces[0], [SQRT_2, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn rectangle_hides_one_diagonal_per_triangle() {
+        let rectangle = Mesh::from(Rectangle::new(2.0, 1.0));
+        let faces = rectangle.compute_face_data();
+        let masks = rectangle.edge_mask_for_quads();
+        assert_eq!(masks.len(), 2);
+        for (mask, face) in masks.iter().zip(&faces) {
+            assert_eq!(mask.count_ones(), 1);
+            let diagonal = face[mask.trailing_zeros() as usize];
+            assert!((diagonal - 5.0_f32.sqrt()).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cuboid_hides_the_diagonal_of_each_face() {
+        let masks = Mesh::from(Cuboid::default()).edge_mask_for_quads();
+        assert_eq!(masks.len(), 12);
+        assert!(masks.iter().all(|mask| mask.count_ones() == 1));
+    }
+
+    #[test]
+    fn circle_fan_keeps_its_spokes() {
+        let masks = Mesh::from(Circle::new(1.0)).edge_mask_for_quads();
+        assert!(!masks.is_empty());
+        assert!(masks.iter().all(|mask| *mask == 0));
+    }
+
+    #[test]
+    fn adjacency_welds_duplicated_vertices() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let triangles = [[0, 1, 2], [3, 4, 5]];
+        let adjacency = Adjacency::new(&positions, &triangles);
+        assert_eq!(
+            adjacency.neighbors,
+            vec![[None, Some(1), None], [None, None, Some(0)]]
+        );
+        assert_eq!(adjacency.first_edges, vec![[0, 1, 2], [3, 4, 1]]);
+        assert_eq!(
+            adjacency.neighbor_index(0),
+            [NO_NEIGHBOR, 1, NO_NEIGHBOR, NO_NEIGHBOR]
+        );
+    }
+
+    #[test]
+    fn adjacency_leaves_shared_edges_as_boundaries() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let triangles = [[0, 1, 2], [1, 0, 3], [0, 1, 4]];
+        let adjacency = Adjacency::new(&positions, &triangles);
+        assert!(adjacency.neighbors.iter().flatten().all(Option::is_none));
+        assert_eq!(adjacency.first_edges[2][2], 2);
+    }
+
+    #[test]
+    fn closed_cuboid_has_no_boundary() {
+        let cuboid = Mesh::from(Cuboid::default());
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            cuboid.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("cuboid without positions");
+        };
+        let adjacency = Adjacency::new(positions, &triangles(&cuboid));
+        assert!(adjacency.neighbors.iter().flatten().all(Option::is_some));
+    }
+
+    #[test]
+    fn crease_angles_of_a_fold() {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let triangles = [[0, 1, 2], [1, 0, 3]];
+        let adjacency = Adjacency::new(&positions, &triangles);
+        let creases = crease_angles(&positions, &triangles, &adjacency);
+        for crease in creases {
+            assert_near(crease, [BOUNDARY_CREASE, BOUNDARY_CREASE, FRAC_PI_2, 0.0]);
+        }
+    }
+
+    #[test]
+    fn triangle_list_indices_of_lists() {
+        let positions = vec![[0.0; 3]; 4];
+        let unindexed = mesh(PrimitiveTopology::TriangleList, positions.clone());
+        assert_eq!(triangle_list_indices(&unindexed), None);
+        assert_eq!(triangles(&unindexed), vec![[0, 1, 2]]);
+
+        let indexed = mesh(PrimitiveTopology::TriangleList, positions)
+            .with_inserted_indices(Indices::U16(vec![0, 1, 2, 2, 1, 3]));
+        assert_eq!(
+            triangle_list_indices(&indexed),
+            Some(vec![0, 1, 2, 2, 1, 3])
+        );
+    }
+
+    #[test]
+    fn triangle_list_indices_of_strips() {
+        let positions = vec![[0.0; 3]; 7];
+        let unindexed = mesh(PrimitiveTopology::TriangleStrip, positions[..4].to_vec());
+        assert_eq!(
+            triangle_list_indices(&unindexed),
+            Some(vec![0, 1, 2, 2, 1, 3])
+        );
+
+        let restarted = mesh(PrimitiveTopology::TriangleStrip, positions)
+            .with_inserted_indices(Indices::U16(vec![0, 1, 2, 3, u16::MAX, 4, 5, 6]));
+        assert_eq!(
+            triangle_list_indices(&restarted),
+            Some(vec![0, 1, 2, 2, 1, 3, 4, 5, 6])
+        );
+    }
+
+    #[test]
+    fn with_barycentric_splits_strips_at_restart_indices() {
+        let positions = vec![[0.0; 3]; 7];
+        let restarted = mesh(PrimitiveTopology::TriangleStrip, positions)
+            .with_inserted_indices(Indices::U32(vec![0, 1, 2, 3, u32::MAX, 4, 5, 6]));
+        let mesh = with_barycentric(restarted);
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+        assert!(mesh.indices().is_none());
+        assert_eq!(mesh.count_vertices(), 9);
+        let Some(VertexAttributeValues::Float32x3(bary)) = mesh.attribute(ATTRIBUTE_BARYCENTRIC)
+        else {
+            panic!("no barycentric coordinates");
+        };
+        for triangle in bary.chunks_exact(3) {
+            assert_eq!(
+                triangle,
+                [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            );
+        }
+    }
+
+    #[test]
+    fn line_segments_of_lists_and_strips() {
+        let positions = vec![[0.0; 3]; 5];
+        let list = mesh(PrimitiveTopology::LineList, positions[..4].to_vec());
+        assert_eq!(line_list_indices(&list), None);
+        assert_eq!(line_segments(&list), vec![[0, 1], [2, 3]]);
+
+        let strip = mesh(PrimitiveTopology::LineStrip, positions.clone())
+            .with_inserted_indices(Indices::U32(vec![0, 1, 2, u32::MAX, 3, 4]));
+        assert_eq!(line_list_indices(&strip), Some(vec![0, 1, 1, 2, 3, 4]));
+        assert_eq!(line_segments(&strip), vec![[0, 1], [1, 2], [3, 4]]);
+
+        let triangle = mesh(PrimitiveTopology::TriangleList, positions);
+        assert_eq!(line_list_indices(&triangle), None);
+        assert!(line_segments(&triangle).is_empty());
+    }
+}
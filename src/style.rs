@@ -0,0 +1,339 @@
+use bevy::{
+    color::Color,
+    ecs::{component::Component, query::QueryItem, system::Resource},
+    math::Vec4,
+    render::{
+        camera::Camera, extract_component::ExtractComponent, render_resource::ShaderType,
+        view::RenderLayers,
+    },
+};
+
+/// How a wireframe entity is drawn.
+///
+/// Entities without this component are drawn with [`WireframeStyle::default`].
+#[derive(Component, Clone, Debug)]
+pub struct WireframeStyle {
+    /// Color of the wire, unless the mesh has an
+    /// [`ATTRIBUTE_WIRE_COLOR`](crate::mesh::ATTRIBUTE_WIRE_COLOR) or
+    /// [`ATTRIBUTE_EDGE_COLOR`](crate::mesh::ATTRIBUTE_EDGE_COLOR).
+    pub wire_color: Color,
+    /// Color of the triangle interiors.
+    pub fill_color: Color,
+    /// Width of the wire in [`WireframeStyle::space`] units. A width of 1.0
+    /// matches the paper.
+    pub line_width: f32,
+    /// How quickly the wire fades into the fill. The paper uses 2.0 in its
+    /// `exp2(-2 * d * d)` falloff.
+    pub sharpness: f32,
+    /// The dashes or dots of the wire. `None` draws a solid wire.
+    pub dash: Option<DashPattern>,
+    /// The units of the line width.
+    pub space: WireframeSpace,
+    /// How the wires and fill are drawn.
+    pub mode: WireframeMode,
+    /// Which triangle edges get a wire.
+    pub edges: WireframeEdges,
+    /// Added to the sort key of the wireframe, so a larger bias draws it
+    /// after other transparent items at the same depth.
+    pub depth_bias: f32,
+}
+
+impl WireframeStyle {
+    /// Wires in `wire_color` over the entity's own material
+    ///
+    /// The small depth bias sorts the wires after a 2d material at the same
+    /// depth.
+    pub fn overlay(wire_color: Color) -> Self {
+        Self {
+            wire_color,
+            mode: WireframeMode::Overlay,
+            depth_bias: 0.001,
+            ..Self::default()
+        }
+    }
+}
+
+/// Draws every mesh in the scene as a wireframe, like bevy's own
+/// `WireframeConfig`
+///
+/// Entities with a [`NoWireframe`] are left alone.
+#[derive(Resource, Clone, Debug)]
+pub struct WireframeSettings {
+    /// Whether every mesh is drawn as a wireframe, not only those marked with
+    /// [`WireframeMesh2d`](crate::wireframe2d::WireframeMesh2d) or
+    /// [`WireframeMesh3d`](crate::wireframe3d::WireframeMesh3d).
+    pub global: bool,
+    /// The style of the unmarked meshes without a [`WireframeStyle`]. It
+    /// defaults to [`WireframeStyle::overlay`] so their materials still show.
+    pub global_style: WireframeStyle,
+}
+
+impl Default for WireframeSettings {
+    fn default() -> Self {
+        Self {
+            global: false,
+            global_style: WireframeStyle::overlay(Color::WHITE),
+        }
+    }
+}
+
+/// Keeps an entity from being drawn as a wireframe, even when it is marked or
+/// [`WireframeSettings::global`] is set
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct NoWireframe;
+
+/// Limits the wireframes a camera draws to the entities on its layers
+///
+/// Cameras without this component draw every wireframe they can see. A
+/// wireframe entity is on the entity's own [`RenderLayers`], or layer 0 if it
+/// has none. The mesh itself is still drawn by any camera that sees it, so a
+/// debug camera can show the wireframes that a gameplay camera with
+/// [`WireframeView::none`] hides.
+#[derive(Component, ExtractComponent, Clone, Debug, Default)]
+pub struct WireframeView {
+    /// The layers whose wireframes the camera draws.
+    pub layers: RenderLayers,
+}
+
+impl WireframeView {
+    /// A camera that draws no wireframes
+    pub fn none() -> Self {
+        Self {
+            layers: RenderLayers::none(),
+        }
+    }
+
+    /// Whether the camera draws the wireframe of an entity on `layers`
+    pub fn shows(&self, layers: &RenderLayers) -> bool {
+        self.layers.intersects(layers)
+    }
+}
+
+/// The number of physical pixels in a logical pixel of a view's render target,
+/// which [`WireframeSpace::Screen`] lengths are scaled by
+#[derive(Component, Clone, Copy, Debug)]
+pub(crate) struct WireframeScaleFactor(pub f32);
+
+impl ExtractComponent for WireframeScaleFactor {
+    type QueryData = &'static Camera;
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component(camera: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        camera.target_scaling_factor().map(WireframeScaleFactor)
+    }
+}
+
+/// Which edges of a triangle mesh are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WireframeEdges {
+    /// Every triangle edge.
+    #[default]
+    All,
+    /// Every edge except those hidden by the mesh's
+    /// [`ATTRIBUTE_EDGE_MASK`](crate::mesh::ATTRIBUTE_EDGE_MASK), or by default
+    /// the diagonals of its quads, so polygons keep their original outline.
+    Polygons,
+    /// Only the edges whose neighboring triangles meet at more than `angle`
+    /// radians, and the boundary edges.
+    Creases { angle: f32 },
+}
+
+impl WireframeEdges {
+    fn bits(&self, mode: WireframeMode) -> u32 {
+        if mode == WireframeMode::Outline {
+            return 3;
+        }
+        match self {
+            WireframeEdges::All => 0,
+            WireframeEdges::Polygons => 1,
+            WireframeEdges::Creases { .. } => 2,
+        }
+    }
+
+    fn crease_angle(&self) -> f32 {
+        match self {
+            WireframeEdges::Creases { angle } => *angle,
+            _ => 0.0,
+        }
+    }
+}
+
+/// How a wireframe is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WireframeMode {
+    /// Wires and fill in a single pass, the first method of the paper.
+    #[default]
+    Shaded,
+    /// A fill pass followed by a pass that draws only the visible edges, the
+    /// second method of the paper. In 3d back faces are culled, and a
+    /// transparent fill color only writes depth.
+    ///
+    /// In 3d the fills of every entity are drawn before any edges, so they
+    /// hide the edges of other entities too, along with anything transparent
+    /// behind them. 2d meshes have no depth buffer, so each entity's fill
+    /// and edges are painted over those of the entities below it.
+    HiddenLine,
+    /// Wires on the boundary edges, those used by only one triangle, and in 3d
+    /// on the silhouette edges of each view. [`WireframeStyle::edges`] is
+    /// ignored.
+    Outline,
+    /// Only the visible wires, drawn over the entity's own [`Material`] or
+    /// [`Material2d`] without a fill. In 3d back faces are culled, and the
+    /// wires are pulled towards the camera so they don't fight with the
+    /// surface.
+    ///
+    /// [`Material`]: bevy::pbr::Material
+    /// [`Material2d`]: bevy::sprite::Material2d
+    Overlay,
+}
+
+/// A repeating pattern of dashes along each edge
+///
+/// The pattern is mirrored around the middle of each edge, so the triangles on
+/// both sides of an edge agree without knowing which way the edge runs. With
+/// no phase a dash is centered on the middle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DashPattern {
+    /// Length of each dash. Zero draws round dots as wide as the wire.
+    pub on: f32,
+    /// Length of the gap between dashes.
+    pub off: f32,
+    /// How far the pattern is shifted from the ends of each edge towards its
+    /// middle. Both halves move, so a growing phase makes the dashes flow
+    /// into the middle from both ends, and a negative one out of it.
+    pub phase: f32,
+    /// The units of the lengths.
+    pub space: WireframeSpace,
+}
+
+impl DashPattern {
+    /// Dashes and gaps of the same length
+    pub fn dashed(length: f32, space: WireframeSpace) -> Self {
+        Self {
+            on: length,
+            off: length,
+            phase: 0.0,
+            space,
+        }
+    }
+
+    /// Dots `spacing` apart
+    pub fn dotted(spacing: f32, space: WireframeSpace) -> Self {
+        Self {
+            on: 0.0,
+            off: spacing,
+            phase: 0.0,
+            space,
+        }
+    }
+
+    /// Shift the pattern towards the middle of each edge
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.phase = phase;
+        self
+    }
+}
+
+/// The space a [`WireframeStyle`] is measured in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WireframeSpace {
+    /// Model units, so lines scale with the camera and the entity's transform.
+    #[default]
+    Model,
+    /// Logical pixels of the view's render target, so lines keep their width
+    /// at any zoom, window size or scale factor.
+    Screen,
+    /// Physical pixels of the view's render target, so lines keep their width
+    /// at any zoom or window size, but get thinner on displays with a larger
+    /// scale factor.
+    PhysicalScreen,
+}
+
+impl WireframeSpace {
+    /// Whether lengths are measured in pixels of the view's render target
+    pub fn is_screen(self) -> bool {
+        matches!(
+            self,
+            WireframeSpace::Screen | WireframeSpace::PhysicalScreen
+        )
+    }
+}
+
+impl Default for WireframeStyle {
+    /// A red wire over a white fill, one model unit wide with a sharpness of
+    /// 2.0, shaded in a single pass on every edge.
+    fn default() -> Self {
+        Self {
+            wire_color: Color::srgb(1.0, 0.0, 0.0),
+            fill_color: Color::WHITE,
+            line_width: 1.0,
+            sharpness: 2.0,
+            dash: None,
+            space: WireframeSpace::Model,
+            mode: WireframeMode::Shaded,
+            edges: WireframeEdges::All,
+            depth_bias: 0.0,
+        }
+    }
+}
+
+/// The GPU representation of a [`WireframeStyle`]
+#[derive(Clone, Copy, Debug, Default, ShaderType)]
+pub struct WireframeStyleUniform {
+    pub wire_color: Vec4,
+    pub fill_color: Vec4,
+    pub line_width: f32,
+    pub sharpness: f32,
+    pub dash_on: f32,
+    pub dash_off: f32,
+    pub dash_phase: f32,
+    pub dash_space: u32,
+    pub edges: u32,
+    pub crease_angle: f32,
+}
+
+impl WireframeStyleUniform {
+    /// The uniform of a style drawn in a view with a [`WireframeScaleFactor`]
+    ///
+    /// The shader measures the screen in physical pixels, so screen space
+    /// lengths are scaled from logical pixels.
+    pub(crate) fn scaled(style: &WireframeStyle, scale_factor: f32) -> Self {
+        let mut uniform = Self::from(style);
+        if style.space == WireframeSpace::Screen {
+            uniform.line_width *= scale_factor;
+        }
+        if style
+            .dash
+            .is_some_and(|dash| dash.space == WireframeSpace::Screen)
+        {
+            uniform.dash_on *= scale_factor;
+            uniform.dash_off *= scale_factor;
+            uniform.dash_phase *= scale_factor;
+        }
+        uniform
+    }
+}
+
+impl From<&WireframeStyle> for WireframeStyleUniform {
+    fn from(style: &WireframeStyle) -> Self {
+        let dash = style.dash.unwrap_or(DashPattern {
+            on: 0.0,
+            off: 0.0,
+            phase: 0.0,
+            space: WireframeSpace::Model,
+        });
+        Self {
+            wire_color: crate::color_to_vec4(style.wire_color),
+            fill_color: crate::color_to_vec4(style.fill_color),
+            line_width: style.line_width,
+            sharpness: style.sharpness,
+            dash_on: dash.on,
+            dash_off: dash.off,
+            dash_phase: dash.phase,
+            dash_space: dash.space.is_screen().into(),
+            edges: style.edges.bits(style.mode),
+            crease_angle: style.edges.crease_angle(),
+        }
+    }
+}
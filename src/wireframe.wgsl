@@ -1,3 +1,10 @@
+#ifdef WIREFRAME_3D
+// Import the standard 3d mesh uniforms and set their bind groups
+#import bevy_pbr::{
+    mesh_functions,
+    mesh_view_bindings::view,
+}
+#else
 // Import the standard 2d mesh uniforms and set their bind groups
 #import bevy_sprite::mesh2d_functions
 
@@ -5,48 +12,361 @@
     mesh2d_view_bindings::view,
     // mesh2d_bindings::mesh,
 }
+#endif
+
+struct WireframeStyle {
+    wire_color: vec4<f32>,
+    fill_color: vec4<f32>,
+    line_width: f32,
+    sharpness: f32,
+    // No dashes when dash_off is zero
+    dash_on: f32,
+    dash_off: f32,
+    dash_phase: f32,
+    // 0: model, 1: screen
+    dash_space: u32,
+    // 0: all edges, 1: polygons, 2: creases, 3: outline
+    edges: u32,
+    crease_angle: f32,
+};
+// The style of the instance being drawn, set by each entry point
+var<private> style: WireframeStyle;
+// The crease angle of a boundary edge
+const BOUNDARY_CREASE: f32 = 4.0;
+
+#ifdef VERTEX_ATTRIBUTES
+// The style of each instance in the batch of mesh uniforms bound with it
+@group(2) @binding(1) var<uniform> styles: array<WireframeStyle, #{STYLE_BATCH_SIZE}u>;
+#else
 @group(2) @binding(0) var<storage> tri: array<vec4<f32>>;
+// The style of each instance, indexed like the mesh uniforms
+@group(2) @binding(1) var<storage> styles: array<WireframeStyle>;
+// The mesh's vertices, as `f32`s laid out as described by `vertex_layout`
+@group(2) @binding(2) var<storage> positions: array<f32>;
+// The mesh's indices; only read when the mesh is INDEXED
+@group(2) @binding(3) var<storage> indices: array<u32>;
+// Bit `i` hides the edge opposite vertex `i` of a triangle
+@group(2) @binding(4) var<storage> edge_masks: array<u32>;
+// The angle between a triangle and its neighbor across each edge
+@group(2) @binding(5) var<storage> creases: array<vec4<f32>>;
+// The triangle across each edge, or NO_NEIGHBOR across a boundary edge
+@group(2) @binding(6) var<storage> neighbors: array<vec4<u32>>;
+const NO_NEIGHBOR: u32 = 0xffffffffu;
+// The wire color of each vertex; only read with WIRE_COLORS
+@group(2) @binding(7) var<storage> wire_colors: array<vec4<f32>>;
+// The color of each edge of each triangle; only read with EDGE_COLORS
+@group(2) @binding(8) var<storage> edge_colors: array<vec4<f32>>;
+// The stride between vertices and the offset of the position in each vertex,
+// in `f32`s, the number of vertices, and whether the mesh has indices:
+// (stride, offset, vertex_count, indexed)
+@group(2) @binding(9) var<uniform> vertex_layout: vec4<u32>;
+#endif
+
+/// The style of the `i`th instance
+fn instance_style(i: u32) -> WireframeStyle {
+#ifdef VERTEX_ATTRIBUTES
+    return styles[min(i, #{STYLE_BATCH_SIZE}u - 1u)];
+#else
+    return styles[i];
+#endif
+}
 
 // The structure of the vertex buffer is as specified in `specialize()`
 struct Vertex {
     @builtin(instance_index) instance_index: u32,
+    // Indexed meshes are drawn without their index buffer, so this is always
+    // the position in the triangle list. Lines are drawn as six vertices, two
+    // triangles, for the quad of each segment.
     @builtin(vertex_index) id : u32,
+#ifndef PULL_VERTICES
     @location(0) position: vec3<f32>,
+#endif
+#ifdef VERTEX_ATTRIBUTES
+#ifdef LINES
+    // The other end of the segment of the quad
+    @location(2) segment_end: vec3<f32>,
+#else
+    // The face data baked into the vertices of each triangle
+    @location(1) bary: vec3<f32>,
+    @location(2) face: vec4<f32>,
+    @location(3) creases: vec3<f32>,
+    @location(4) edge_mask: u32,
+#endif
+#ifdef WIRE_COLORS
+    @location(5) wire_color: vec4<f32>,
+#endif
+#endif
 };
 
+#ifndef VERTEX_ATTRIBUTES
+
+/// The position of the `vertex`th vertex of the mesh
+fn vertex_position(vertex: u32) -> vec3<f32> {
+    let i = vertex * vertex_layout.x + vertex_layout.y;
+    return vec3<f32>(positions[i], positions[i + 1u], positions[i + 2u]);
+}
+
+/// The position of the `i`th vertex of the triangle list
+fn triangle_vertex(i: u32) -> vec3<f32> {
+#ifdef INDEXED
+    return vertex_position(indices[i]);
+#else
+    return vertex_position(i);
+#endif
+}
+
+/// The wire color of the `i`th vertex of the triangle list
+fn triangle_vertex_color(i: u32) -> vec4<f32> {
+#ifdef WIRE_COLORS
+#ifdef INDEXED
+    return wire_colors[indices[i]];
+#else
+    return wire_colors[i];
+#endif
+#else
+    return style.wire_color;
+#endif
+}
+#endif
+
 struct VertexOutput {
     // The vertex shader must set the on-screen position of the vertex
     @builtin(position) clip_position: vec4<f32>,
-    // We pass the vertex color to the fragment shader in location 0
+    // We pass the distances to the edges to the fragment shader in location 0
+#ifdef LINES
+    // The pixels across and along a segment from its start
+    @location(0) @interpolate(linear) dist: vec4<f32>,
+#else ifdef SCREEN_SPACE
+    @location(0) @interpolate(linear) dist: vec4<f32>,
+#else
     @location(0) dist: vec4<f32>,
+#endif
     @location(1) bary: vec3<f32>,
+    // The lengths of the triangle's edges, or for lines the pixel length and
+    // width of the segment and its pixels per model unit
+    @location(2) @interpolate(flat) lengths: vec3<f32>,
+    // The edges to hide
+    @location(3) @interpolate(flat) mask: u32,
+    // The lengths of the triangle's edges in the units of the dash pattern
+    @location(4) @interpolate(flat) dash_lengths: vec3<f32>,
+    // The wire color, blended between the vertices of each edge
+    @location(5) wire_color: vec4<f32>,
+    @location(6) @interpolate(flat) triangle: u32,
+    @location(7) @interpolate(flat) instance_index: u32,
+#ifdef VERTEX_ATTRIBUTES
+    // Twice the area of the triangle
+    @location(8) @interpolate(flat) area: f32,
+#endif
 };
 
+fn local_to_clip(model: mat4x4<f32>, position: vec3<f32>) -> vec4<f32> {
+#ifdef WIREFRAME_3D
+    return mesh_functions::mesh_position_local_to_clip(model, vec4<f32>(position, 1.0));
+#else
+    return mesh2d_functions::mesh2d_position_local_to_clip(model, vec4<f32>(position, 1.0));
+#endif
+}
+
+/// Project a vertex of the triangle into viewport pixels. This is the paper's
+/// `WIN_SCALE * gl_Position.xy / gl_Position.w`.
+fn local_to_window(model: mat4x4<f32>, position: vec3<f32>) -> vec2<f32> {
+    let clip = local_to_clip(model, position);
+    return 0.5 * view.viewport.zw * clip.xy / clip.w;
+}
+
+#ifdef WIREFRAME_3D
+#ifndef VERTEX_ATTRIBUTES
+/// Whether a triangle faces the view, from the winding of its projection
+fn front_facing(model: mat4x4<f32>, t: u32) -> bool {
+    let a = local_to_clip(model, triangle_vertex(t * 3));
+    let b = local_to_clip(model, triangle_vertex(t * 3 + 1));
+    let c = local_to_clip(model, triangle_vertex(t * 3 + 2));
+    let e1 = b.xy / b.w - a.xy / a.w;
+    let e2 = c.xy / c.w - a.xy / a.w;
+    return e1.x * e2.y - e1.y * e2.x > 0.0;
+}
+#endif
+#endif
+
+#ifdef LINES
+/// A corner of the quad a line segment is drawn on, as wide in the viewport as
+/// the falloff of its wire and a pixel more for antialiasing
+fn line_vertex(vertex: Vertex, model: mat4x4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.instance_index = vertex.instance_index;
+    let segment = vertex.id / 6u;
+    let corner = vertex.id % 6u;
+    // Corners 1, 2 and 4 are at the end of the segment, and corners 2, 4 and 5
+    // on its left, so both triangles wind counterclockwise.
+    let end = u32(corner == 1u || corner == 2u || corner == 4u);
+    let side = select(-1.0, 1.0, corner == 2u || corner >= 4u);
+#ifdef VERTEX_ATTRIBUTES
+    let a = select(vertex.position, vertex.segment_end, end == 1u);
+    let b = select(vertex.segment_end, vertex.position, end == 1u);
+#ifdef WIRE_COLORS
+    out.wire_color = vertex.wire_color;
+#else
+    out.wire_color = style.wire_color;
+#endif
+#else
+    let a = triangle_vertex(2u * segment);
+    let b = triangle_vertex(2u * segment + 1u);
+    out.wire_color = triangle_vertex_color(2u * segment + end);
+#endif
+    let pa = local_to_window(model, a);
+    let pb = local_to_window(model, b);
+    let len = length(pb - pa);
+    let dir = select(vec2<f32>(1.0, 0.0), (pb - pa) / len, len > 0.0);
+    let normal = vec2<f32>(-dir.y, dir.x);
+    let pixels_per_unit = len / max(length(b - a), 1e-30);
+#ifdef SCREEN_SPACE
+    let width = style.line_width;
+#else
+    let width = style.line_width * pixels_per_unit;
+#endif
+    // The wire fades below a 255th beyond `sqrt(8 / sharpness)` widths.
+    let reach = width * min(sqrt(8.0 / max(style.sharpness, 1e-3)), 16.0) + 1.0;
+    let along = select(-reach, len + reach, end == 1u);
+    let offset = side * reach * normal + select(-reach, reach, end == 1u) * dir;
+    let clip = local_to_clip(model, select(a, b, end == 1u));
+    out.clip_position = clip + vec4<f32>(offset / (0.5 * view.viewport.zw) * clip.w, 0.0, 0.0);
+    out.dist = vec4<f32>(side * reach, along, 0.0, f32(segment));
+    out.lengths = vec3<f32>(len, width, pixels_per_unit);
+    out.triangle = segment;
+    return out;
+}
+#endif
+
 /// Entry point for the vertex shader
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
+    style = instance_style(vertex.instance_index);
+#ifdef WIREFRAME_3D
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+#else
+    let model = mesh2d_functions::get_model_matrix(vertex.instance_index);
+#endif
+#ifdef LINES
+    return line_vertex(vertex, model);
+#else
     var out: VertexOutput;
+    out.instance_index = vertex.instance_index;
     // Project the world position of the mesh into screen position
     let ti = vertex.id / 3;
     let vi = vertex.id % 3;
     out.bary = vec3<f32>(f32(vi == 0u), f32(vi == 1u), f32(vi == 2u));
-    let model = mesh2d_functions::get_model_matrix(vertex.instance_index);
-    out.clip_position = mesh2d_functions::mesh2d_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+#ifdef DEFORMED
+    // Pull the vertex from the entity's deformed positions.
+    let position = triangle_vertex(vertex.id);
+#else ifdef INDEXED
+    // Pull the vertex through the index buffer.
+    let position = triangle_vertex(vertex.id);
+#else
+    let position = vertex.position;
+#endif
+    out.clip_position = local_to_clip(model, position);
+#ifdef VERTEX_ATTRIBUTES
+#ifdef WIRE_COLORS
+    out.wire_color = vertex.wire_color;
+#else
+    out.wire_color = style.wire_color;
+#endif
+#else
+    out.wire_color = triangle_vertex_color(vertex.id);
+#endif
+    out.triangle = ti;
+#ifdef VERTEX_ATTRIBUTES
+    out.bary = vertex.bary;
+    let edge_mask = vertex.edge_mask;
+    let crease_angles = vertex.creases;
+#else
+    let edge_mask = edge_masks[ti];
+    let crease_angles = creases[ti].xyz;
+#endif
+    if style.edges == 1u {
+        out.mask = edge_mask;
+    } else if style.edges == 2u {
+        let crease = crease_angles < vec3<f32>(style.crease_angle);
+        out.mask = select(0u, 1u, crease.x) | select(0u, 2u, crease.y) | select(0u, 4u, crease.z);
+    } else if style.edges == 3u {
+#ifdef VERTEX_ATTRIBUTES
+        // Without the neighbors of the triangle, only its boundary edges are
+        // outlined.
+        let inner = crease_angles < vec3<f32>(BOUNDARY_CREASE);
+        out.mask = select(0u, 1u, inner.x) | select(0u, 2u, inner.y) | select(0u, 4u, inner.z);
+#else
+        // Hide the edges that are neither on the boundary nor, in 3d, on the
+        // silhouette.
+#ifdef WIREFRAME_3D
+        let facing = front_facing(model, ti);
+#endif
+        for (var k = 0u; k < 3u; k++) {
+            let n = neighbors[ti][k];
+            if n == NO_NEIGHBOR {
+                continue;
+            }
+#ifdef WIREFRAME_3D
+            if front_facing(model, n) != facing {
+                continue;
+            }
+#endif
+            out.mask |= 1u << k;
+        }
+#endif
+    }
+#ifdef VERTEX_ATTRIBUTES
+    // The screen is measured in the fragment shader, which can't see the
+    // other corners of the triangle.
+    out.lengths = vertex.face.xyz;
+    out.dist = vec4<f32>(vertex.face.w / vertex.face.xyz * out.bary, f32(ti));
+    out.dash_lengths = vertex.face.xyz;
+    out.area = vertex.face.w;
+#else
+    // The face buffer is in model units, so measure the triangle in this view
+    // for anything in screen space.
+    let p0 = local_to_window(model, triangle_vertex(ti * 3));
+    let p1 = local_to_window(model, triangle_vertex(ti * 3 + 1));
+    let p2 = local_to_window(model, triangle_vertex(ti * 3 + 2));
+    let v0 = p2 - p1;
+    let v1 = p2 - p0;
+    let v2 = p1 - p0;
+    let window_lengths = vec3<f32>(length(v0), length(v1), length(v2));
+#ifdef SCREEN_SPACE
+    let area = abs(v1.x * v2.y - v1.y * v2.x);
+    out.lengths = window_lengths;
+    out.dist = vec4<f32>(area / out.lengths * out.bary, f32(ti));
+#else
+    out.lengths = tri[ti].xyz;
     out.dist = vec4<f32>(tri[ti].w/tri[ti].xyz * out.bary, f32(ti));
+#endif
+    out.dash_lengths = select(tri[ti].xyz, window_lengths, style.dash_space == 1u);
+#endif
     return out;
+#endif
 }
 
 // The input of the fragment shader must correspond to the output of the vertex shader for all `location`s
 struct FragmentInput {
     @builtin(position) position: vec4<f32>,
-    // The color is interpolated between vertices by default
-    // @location(0) color: vec4<f32>,
+#ifdef LINES
+    @location(0) @interpolate(linear) dist: vec4<f32>,
+#else ifdef SCREEN_SPACE
+    @location(0) @interpolate(linear) dist: vec4<f32>,
+#else
     @location(0) dist: vec4<f32>,
+#endif
     @location(1) bary: vec3<f32>,
+    @location(2) @interpolate(flat) lengths: vec3<f32>,
+    @location(3) @interpolate(flat) mask: u32,
+    @location(4) @interpolate(flat) dash_lengths: vec3<f32>,
+    @location(5) wire_color: vec4<f32>,
+    @location(6) @interpolate(flat) triangle: u32,
+    @location(7) @interpolate(flat) instance_index: u32,
+#ifdef VERTEX_ATTRIBUTES
+    @location(8) @interpolate(flat) area: f32,
+#endif
 };
 
-const WIRE_COL: vec4<f32> = vec4(0.0, 0.0, 1.0, 1.0);
-
 fn min_index(v: vec3<f32>) -> u32 {
    var i: u32 = 0;
    for (var j: u32 = 1; j < 3; j++) {
@@ -56,31 +376,89 @@ fn min_index(v: vec3<f32>) -> u32 {
    }
    return i;
 }
-const pi = 3.14159265359;
 
 /// Entry point for the fragment shader
 @fragment
 fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
-    let color = vec4<f32>(1.0, 1.0, 0.0, 1.0);
-    let dist = in.dist.xyz;
+    style = instance_style(in.instance_index);
+#ifdef LINES
+    // Lines have no faces; they are drawn as plain wires, measured from the
+    // nearest point of their segment so their ends are round.
+    let len = in.lengths.x;
+    let width = in.lengths.y;
+    let along = in.dist.y;
+    let past = max(max(-along, along - len), 0.0);
+    let d = length(vec2<f32>(in.dist.x, past)) / width;
+    var gap = 0.0;
+    if style.dash_off > 0.0 {
+        let period = style.dash_on + style.dash_off;
+        // The pixels per unit of the dash pattern
+        let scale = select(in.lengths.z, 1.0, style.dash_space == 1u);
+        let s = clamp(along, 0.0, len) / scale;
+        let t = fract((s + 0.5 * style.dash_on + style.dash_phase) / period) * period;
+        let outside = max(min(t - style.dash_on, period - t), 0.0);
+        gap = outside * scale / width;
+    }
+    let I = exp2(-style.sharpness * (d * d + gap * gap));
+    return vec4<f32>(in.wire_color.rgb, in.wire_color.a * I);
+#else ifdef FILL_PASS
+    // The fill of a hidden line drawing only hides the edges behind it.
+    return style.fill_color;
+#else
+    var dist = in.dist.xyz;
+    var lengths = in.lengths;
+#ifdef VERTEX_ATTRIBUTES
+    // The screen size of the triangle, from how the barycentric coordinates
+    // change between pixels: the distance to edge `i` is `bary[i] / fwidth`
+    // pixels, and `bary[i] * area / lengths[i]` in model units.
+    let bary_width = fwidth(in.bary);
+    let window_lengths = in.lengths * in.lengths / (in.area * bary_width);
+    let dash_lengths = select(in.lengths, window_lengths, style.dash_space == 1u);
+#ifdef SCREEN_SPACE
+    dist = in.bary / bary_width;
+    lengths = window_lengths;
+#endif
+#else
+    let dash_lengths = in.dash_lengths;
+#endif
+    // Hidden edges are infinitely far away.
+    for (var k = 0u; k < 3u; k++) {
+        if (in.mask & (1u << k)) != 0u {
+            dist[k] = 1e30;
+        }
+    }
     let i = min_index(dist.xyz);
-    let j = (i + 1) % 3;
     //let d = min(dist[0], min(dist[1], dist[2]));
-    let d = dist[i];
-    var I = exp2(-2.0 * d * d);
-    var k = 1.0;
-    if i == 1 {
-            k = -1.0;
-    }
-    var wire_color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
-    wire_color[i] = 1.0;
+    let d = dist[i] / style.line_width;
 
-
-    /// This is still a special case where the model space and pixel space are
-    /// equivalent.
-    let width_pixel = 10.0;
-    var length = tri[u32(in.dist.w)][i];
-
-    I *= step(sin(k * in.bary[j] * length * pi / width_pixel), -0.01);
-    return I * wire_color + (1.0 - I) * color;
+    // The distance along the edge to the nearest dash, which falls off like
+    // the distance to the edge so dashes are antialiased the same way.
+    var gap = 0.0;
+    if style.dash_off > 0.0 {
+        let period = style.dash_on + style.dash_off;
+        let dash_length = dash_lengths[i];
+        // Measure from the middle of the edge so the triangles on both sides
+        // of it agree. The pattern is mirrored there, so the phase moves the
+        // dashes centered `k * period - phase` from the middle towards it.
+        let s = abs(in.bary[(i + 2) % 3] - 0.5) * dash_length;
+        let t = fract((s + 0.5 * style.dash_on + style.dash_phase) / period) * period;
+        let outside = max(min(t - style.dash_on, period - t), 0.0);
+        gap = outside * lengths[i] / dash_length / style.line_width;
+    }
+    var I = exp2(-style.sharpness * (d * d + gap * gap));
+#ifdef EDGE_COLORS
+    let wire_color = edge_colors[in.triangle * 3u + i];
+#else
+    let wire_color = in.wire_color;
+#endif
+#ifdef EDGE_PASS
+    // Draw only the wire over the fill pass.
+    if I < 1.0 / 255.0 {
+        discard;
+    }
+    return vec4<f32>(wire_color.rgb, wire_color.a * I);
+#else
+    return I * wire_color + (1.0 - I) * style.fill_color;
+#endif
+#endif
 }
@@ -1,44 +1,63 @@
-use crate::compute::*;
+use crate::{
+    backend::{init_backend, BakedMeshes, WireframeBackend},
+    compute::*,
+    deform::DeformedFaces,
+    mesh::{
+        ATTRIBUTE_BARYCENTRIC, ATTRIBUTE_CREASE_ANGLES, ATTRIBUTE_EDGE_COLOR, ATTRIBUTE_EDGE_MASK,
+        ATTRIBUTE_FACE, ATTRIBUTE_SEGMENT_END, ATTRIBUTE_WIRE_COLOR,
+    },
+    style::{
+        NoWireframe, WireframeMode, WireframeScaleFactor, WireframeSettings, WireframeSpace,
+        WireframeStyle, WireframeStyleUniform, WireframeView,
+    },
+};
 use bevy::{
-    app::{App, Plugin},
-    asset::{embedded_asset, DirectAssetAccessExt, Handle},
+    app::{App, Last, Plugin},
+    asset::{embedded_asset, AssetId, DirectAssetAccessExt, Handle},
     core_pipeline::core_2d::Transparent2d,
     ecs::{
         component::Component,
         entity::Entity,
-        query::With,
+        query::{Has, With, Without},
         schedule::IntoSystemConfigs,
-        system::{lifetimeless::Read, Commands, Local, Query, Res, ResMut, Resource},
+        system::{
+            lifetimeless::{Read, SRes},
+            Commands, Local, Query, Res, ResMut, Resource, SystemParamItem,
+        },
         world::{FromWorld, World},
     },
-    log::warn,
-    math::{FloatOrd, Vec4},
+    log::{error, warn},
+    math::{FloatOrd, UVec4, Vec4},
     prelude::{Deref, DerefMut},
     render::{
-        mesh::{GpuMesh, MeshVertexBufferLayoutRef},
+        extract_component::ExtractComponentPlugin,
+        mesh::{GpuBufferInfo, GpuMesh, Mesh, MeshVertexBufferLayoutRef},
         render_asset::RenderAssets,
         render_phase::{
             AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
-            RenderCommandResult, SetItemPipeline, SortedRenderPhase, TrackedRenderPass,
+            RenderCommandResult, SetItemPipeline, SortedPhaseItem, SortedRenderPhase,
+            TrackedRenderPass,
         },
         render_resource::{
-            binding_types::storage_buffer_read_only, BindGroup, BindGroupEntries, BindGroupLayout,
-            BindGroupLayoutEntries, PipelineCache, PrimitiveTopology, RenderPipelineDescriptor,
-            Shader, ShaderStages, SpecializedMeshPipeline, SpecializedMeshPipelineError,
-            SpecializedMeshPipelines,
+            binding_types::{storage_buffer_read_only, uniform_buffer, uniform_buffer_sized},
+            encase, BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries,
+            BlendState, Buffer, BufferBinding, BufferId, BufferInitDescriptor, BufferUsages,
+            PipelineCache, PrimitiveTopology, RenderPipelineDescriptor, Shader, ShaderDefVal,
+            ShaderSize, ShaderStages, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+            SpecializedMeshPipelines, StorageBuffer,
         },
-        renderer::RenderDevice,
-        view::{ExtractedView, Msaa, ViewVisibility, VisibleEntities},
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, Msaa, RenderLayers, ViewVisibility, VisibleEntities},
         Extract, ExtractSchedule, Render, RenderApp, RenderSet,
     },
     sprite::{
-        extract_mesh2d, DrawMesh2d, Material2dBindGroupId, Mesh2dHandle, Mesh2dPipeline,
-        Mesh2dPipelineKey, Mesh2dTransforms, MeshFlags, RenderMesh2dInstance, SetMesh2dBindGroup,
-        SetMesh2dViewBindGroup, WithMesh2d,
+        extract_mesh2d, DrawMesh2d, Mesh2dHandle, Mesh2dPipeline, Mesh2dPipelineKey,
+        SetMesh2dBindGroup, SetMesh2dViewBindGroup, WithMesh2d,
     },
     transform::components::GlobalTransform,
-    utils::EntityHashMap,
+    utils::{EntityHashMap, HashMap, HashSet},
 };
+use std::{marker::PhantomData, num::NonZeroU64, ops::Range};
 
 #[derive(Component, Default)]
 pub struct WireframeMesh2d;
@@ -47,42 +66,349 @@ pub struct WireframeMesh2d;
 pub struct WireframeMesh2dPipeline {
     /// this pipeline wraps the standard [`Mesh2dPipeline`]
     mesh2d_pipeline: Mesh2dPipeline,
-    shader: Handle<Shader>,
-    wireframe2d_layout: BindGroupLayout,
+    wireframe: WireframeShader,
 }
 
 impl FromWorld for WireframeMesh2dPipeline {
     fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh2d_pipeline: Mesh2dPipeline::from_world(world),
+            wireframe: WireframeShader::from_world(world),
+        }
+    }
+}
+
+/// The wireframe shader and the layout of its face and style bind group,
+/// shared by the wireframe pipelines
+pub(crate) struct WireframeShader {
+    shader: Handle<Shader>,
+    pub(crate) layout: BindGroupLayout,
+    pub(crate) backend: WireframeBackend,
+    /// The number of styles bound with [`WireframeBackend::VertexAttributes`]
+    style_batch_size: u32,
+}
+
+impl FromWorld for WireframeShader {
+    fn from_world(world: &mut World) -> Self {
+        let backend = *world.resource::<WireframeBackend>();
         let render_device = world.resource::<RenderDevice>();
-        let shader = world.load_asset::<Shader>("embedded://bevy_wireframe/wireframe.wgsl");
-        let wireframe2d_layout = render_device.create_bind_group_layout(
-            "Face",
-            &BindGroupLayoutEntries::sequential(
-                ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-                (storage_buffer_read_only::<Vec<Vec4>>(false),),
+        let style_batch_size = WireframeBackend::style_batch_size(render_device);
+        let layout = match backend {
+            WireframeBackend::Compute => render_device.create_bind_group_layout(
+                "Face",
+                &BindGroupLayoutEntries::sequential(
+                    ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    (
+                        storage_buffer_read_only::<Vec<Vec4>>(false),
+                        storage_buffer_read_only::<Vec<WireframeStyleUniform>>(false),
+                        storage_buffer_read_only::<Vec<f32>>(false),
+                        storage_buffer_read_only::<Vec<u32>>(false),
+                        storage_buffer_read_only::<Vec<u32>>(false),
+                        storage_buffer_read_only::<Vec<Vec4>>(false),
+                        storage_buffer_read_only::<Vec<UVec4>>(false),
+                        storage_buffer_read_only::<Vec<Vec4>>(false),
+                        storage_buffer_read_only::<Vec<Vec4>>(false),
+                        uniform_buffer::<UVec4>(false),
+                    ),
+                ),
             ),
-        );
+            // The face data is in the vertices, and the styles are bound at
+            // the dynamic offset of the mesh uniforms.
+            WireframeBackend::VertexAttributes => render_device.create_bind_group_layout(
+                "Face",
+                &BindGroupLayoutEntries::with_indices(
+                    ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ((
+                        1,
+                        uniform_buffer_sized(
+                            true,
+                            NonZeroU64::new(style_batch_bytes(style_batch_size)),
+                        ),
+                    ),),
+                ),
+            ),
+        };
+        let shader = world.load_asset::<Shader>("embedded://bevy_wireframe/wireframe.wgsl");
         Self {
-            mesh2d_pipeline: Mesh2dPipeline::from_world(world),
             shader,
-            wireframe2d_layout,
+            layout,
+            backend,
+            style_batch_size,
+        }
+    }
+}
+
+/// The size of a batch of styles bound with
+/// [`WireframeBackend::VertexAttributes`]
+fn style_batch_bytes(style_batch_size: u32) -> u64 {
+    style_batch_size as u64 * WireframeStyleUniform::SHADER_SIZE.get()
+}
+
+/// Create the bind group of a wireframe mesh from its face data and the styles
+/// of every wireframe instance
+///
+/// A deformed entity's own positions and face data replace those of its mesh.
+pub(crate) fn create_wireframe_bind_group<M>(
+    render_device: &RenderDevice,
+    layout: &BindGroupLayout,
+    pos_buffer: &PosBuffer,
+    deformed: Option<&DeformedFaces>,
+    styles: &WireframeStyles<M>,
+) -> Option<WireframeBindGroup> {
+    let style_binding = styles.buffer.binding()?;
+    let (face_buffer, positions, vertex_layout) = match deformed {
+        Some(deformed) => (
+            &deformed.face_buffer,
+            &deformed.positions,
+            &deformed.vertex_layout,
+        ),
+        None => (
+            &pos_buffer.face_buffer,
+            &pos_buffer.buffer,
+            &pos_buffer.vertex_layout,
+        ),
+    };
+    Some(WireframeBindGroup(render_device.create_bind_group(
+        "wireframe_bind_group",
+        layout,
+        &BindGroupEntries::sequential((
+            face_buffer.as_entire_buffer_binding(),
+            style_binding,
+            positions.as_entire_buffer_binding(),
+            pos_buffer.index_binding().as_entire_buffer_binding(),
+            pos_buffer.edge_mask_buffer.as_entire_buffer_binding(),
+            pos_buffer.crease_buffer.as_entire_buffer_binding(),
+            pos_buffer.neighbor_buffer.as_entire_buffer_binding(),
+            pos_buffer.wire_color_binding().as_entire_buffer_binding(),
+            pos_buffer.edge_color_binding().as_entire_buffer_binding(),
+            vertex_layout.as_entire_buffer_binding(),
+        )),
+    )))
+}
+
+/// Create the bind group of every wireframe drawn with
+/// [`WireframeBackend::VertexAttributes`], which only holds the styles
+pub(crate) fn create_style_bind_group<M>(
+    render_device: &RenderDevice,
+    wireframe: &WireframeShader,
+    styles: &WireframeStyles<M>,
+) -> Option<WireframeBindGroup> {
+    let buffer = styles.uniform.as_ref()?;
+    Some(WireframeBindGroup(render_device.create_bind_group(
+        "wireframe_style_bind_group",
+        &wireframe.layout,
+        &BindGroupEntries::with_indices(((
+            1,
+            BufferBinding {
+                buffer,
+                offset: 0,
+                size: NonZeroU64::new(style_batch_bytes(wireframe.style_batch_size)),
+            },
+        ),)),
+    )))
+}
+
+/// Whether the wireframe of a mesh is drawn through its indices, or `None`
+/// while its face data isn't ready
+pub(crate) fn face_data_indexed(
+    backend: WireframeBackend,
+    pos_buffers: &PosBuffers,
+    id: AssetId<Mesh>,
+    mesh: &GpuMesh,
+    lines: bool,
+) -> Option<bool> {
+    match backend {
+        WireframeBackend::Compute => pos_buffers
+            .get(id)
+            .map(|pos_buffer| pos_buffer.index_buffer.is_some()),
+        // Baked meshes are unindexed, and the quads of lines have no face
+        // data.
+        WireframeBackend::VertexAttributes => {
+            (lines || mesh.layout.0.contains(ATTRIBUTE_FACE)).then_some(false)
+        }
+    }
+}
+
+/// Specializes a wrapped mesh pipeline into a wireframe pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WireframePipelineKey<K> {
+    pub mesh_key: K,
+    pub space: WireframeSpace,
+    /// Whether the mesh has indices, in which case its vertices are pulled
+    /// from storage instead of the vertex buffer
+    pub indexed: bool,
+    /// Whether the mesh is made of lines, whose segments are drawn as plain
+    /// wires on quads around them
+    pub lines: bool,
+    /// Whether the entity is skinned or morphed, in which case its deformed
+    /// vertices are pulled from storage
+    pub deformed: bool,
+    pub pass: WireframePass,
+}
+
+/// Which pass of a [`WireframeMode`] a pipeline draws
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WireframePass {
+    /// The single pass of [`WireframeMode::Shaded`]
+    #[default]
+    Shaded,
+    /// The fill of [`WireframeMode::HiddenLine`], which hides the edges behind it
+    Fill,
+    /// The visible edges of [`WireframeMode::HiddenLine`] and
+    /// [`WireframeMode::Overlay`]
+    Edges,
+}
+
+impl WireframePass {
+    /// The passes to queue, in order, for a mode
+    pub(crate) fn passes(mode: WireframeMode) -> &'static [WireframePass] {
+        match mode {
+            WireframeMode::Shaded | WireframeMode::Outline => &[WireframePass::Shaded],
+            WireframeMode::HiddenLine => &[WireframePass::Fill, WireframePass::Edges],
+            WireframeMode::Overlay => &[WireframePass::Edges],
+        }
+    }
+}
+
+impl<K> WireframePipelineKey<K> {
+    pub(crate) fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        let mut shader_defs = Vec::new();
+        if self.space.is_screen() {
+            shader_defs.push("SCREEN_SPACE".into());
+        }
+        if self.indexed {
+            shader_defs.push("INDEXED".into());
+        }
+        if self.lines {
+            shader_defs.push("LINES".into());
+        }
+        if self.deformed {
+            shader_defs.push("DEFORMED".into());
+        }
+        match self.pass {
+            WireframePass::Shaded => {}
+            WireframePass::Fill => shader_defs.push("FILL_PASS".into()),
+            WireframePass::Edges => shader_defs.push("EDGE_PASS".into()),
+        }
+        shader_defs
+    }
+}
+
+/// The topology a mesh is drawn with as a wireframe, or `None` if it can't be
+///
+/// Triangle strips are drawn through triangle list indices made by the
+/// [`FacePlugin`], and lines as a quad of two triangles for each segment. Points
+/// have no edges.
+pub(crate) fn wireframe_topology(topology: PrimitiveTopology) -> Option<PrimitiveTopology> {
+    match topology {
+        PrimitiveTopology::TriangleList
+        | PrimitiveTopology::TriangleStrip
+        | PrimitiveTopology::LineList
+        | PrimitiveTopology::LineStrip => Some(PrimitiveTopology::TriangleList),
+        PrimitiveTopology::PointList => None,
+    }
+}
+
+/// Whether a mesh is drawn as lines: a line mesh, or with
+/// [`WireframeBackend::VertexAttributes`] the copy of one with the quads of its
+/// segments
+pub(crate) fn is_lines(mesh: &GpuMesh) -> bool {
+    matches!(
+        mesh.primitive_topology(),
+        PrimitiveTopology::LineList | PrimitiveTopology::LineStrip
+    ) || mesh.layout.0.contains(ATTRIBUTE_SEGMENT_END)
+}
+
+/// Swap the wireframe shader and bind group into a descriptor made by a
+/// wrapped mesh pipeline
+///
+/// The wire colors of the mesh's [`ATTRIBUTE_WIRE_COLOR`] and
+/// [`ATTRIBUTE_EDGE_COLOR`] are used when its vertex layout has them. With
+/// [`WireframeBackend::VertexAttributes`], the face data is read from the
+/// attributes baked into the mesh.
+pub(crate) fn wireframe_descriptor<K>(
+    descriptor: &mut RenderPipelineDescriptor,
+    wireframe: &WireframeShader,
+    key: &WireframePipelineKey<K>,
+    vertex_layout: &MeshVertexBufferLayoutRef,
+    mut shader_defs: Vec<ShaderDefVal>,
+) -> Result<(), SpecializedMeshPipelineError> {
+    shader_defs.extend(key.shader_defs());
+    let wire_colors = vertex_layout.0.contains(ATTRIBUTE_WIRE_COLOR);
+    if wire_colors {
+        shader_defs.push("WIRE_COLORS".into());
+    }
+    match wireframe.backend {
+        WireframeBackend::Compute => {
+            if vertex_layout.0.contains(ATTRIBUTE_EDGE_COLOR) && !key.lines {
+                shader_defs.push("EDGE_COLORS".into());
+            }
+            // Lines are widened from both ends of their segments.
+            if key.indexed || key.lines {
+                shader_defs.push("PULL_VERTICES".into());
+                descriptor.vertex.buffers.clear();
+            }
+        }
+        WireframeBackend::VertexAttributes => {
+            shader_defs.push("VERTEX_ATTRIBUTES".into());
+            shader_defs.push(ShaderDefVal::UInt(
+                "STYLE_BATCH_SIZE".into(),
+                wireframe.style_batch_size,
+            ));
+            let mut attributes = vec![Mesh::ATTRIBUTE_POSITION.at_shader_location(0)];
+            if key.lines {
+                attributes.push(ATTRIBUTE_SEGMENT_END.at_shader_location(2));
+            } else {
+                attributes.extend([
+                    ATTRIBUTE_BARYCENTRIC.at_shader_location(1),
+                    ATTRIBUTE_FACE.at_shader_location(2),
+                    ATTRIBUTE_CREASE_ANGLES.at_shader_location(3),
+                    ATTRIBUTE_EDGE_MASK.at_shader_location(4),
+                ]);
+            }
+            if wire_colors {
+                attributes.push(ATTRIBUTE_WIRE_COLOR.at_shader_location(5));
+            }
+            descriptor.vertex.buffers = vec![vertex_layout.0.get_layout(&attributes)?];
+        }
+    }
+    descriptor.layout.push(wireframe.layout.clone());
+    let shader = &wireframe.shader;
+    descriptor.vertex.shader = shader.clone();
+    descriptor.vertex.shader_defs.extend(shader_defs.iter().cloned());
+    let fragment = descriptor.fragment.as_mut().unwrap();
+    fragment.shader = shader.clone();
+    fragment.shader_defs.extend(shader_defs);
+
+    if key.pass != WireframePass::Shaded {
+        for target in fragment.targets.iter_mut().flatten() {
+            target.blend = Some(BlendState::ALPHA_BLENDING);
+        }
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            // The fill writes depth even when it is transparent, so the edges
+            // behind it fail the depth test.
+            depth_stencil.depth_write_enabled = key.pass == WireframePass::Fill;
+            if key.pass == WireframePass::Edges {
+                // Pull the edges towards the camera over their own fill.
+                depth_stencil.bias.constant = 1;
+                depth_stencil.bias.slope_scale = 1.0;
+            }
         }
     }
+    Ok(())
 }
 
 // We implement `SpecializedPipeline` to customize the default rendering from `Mesh2dPipeline`
 impl SpecializedMeshPipeline for WireframeMesh2dPipeline {
-    type Key = Mesh2dPipelineKey;
+    type Key = WireframePipelineKey<Mesh2dPipelineKey>;
 
     fn specialize(
         &self,
         key: Self::Key,
         layout: &MeshVertexBufferLayoutRef,
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
-        let mut descriptor = self.mesh2d_pipeline.specialize(key, layout)?;
-        descriptor.layout.push(self.wireframe2d_layout.clone());
-        descriptor.vertex.shader = self.shader.clone();
-        descriptor.fragment.as_mut().unwrap().shader = self.shader.clone();
+        let mut descriptor = self.mesh2d_pipeline.specialize(key.mesh_key, layout)?;
+        wireframe_descriptor(&mut descriptor, &self.wireframe, &key, layout, Vec::new())?;
         descriptor.label = Some("wireframe_mesh2d_pipeline".into());
         Ok(descriptor)
     }
@@ -102,31 +428,85 @@ type DrawWireframeMesh2d = (
     DrawMesh2d,
 );
 
+// This specifies how to render an indexed 2d mesh
+type DrawIndexedWireframeMesh2d = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetMesh2dBindGroup<1>,
+    SetFaceBindGroup<2>,
+    // Draw the mesh's triangles without its index buffer
+    DrawMeshFaces,
+);
+
+// This specifies how to render a 2d mesh from its baked copy
+type DrawBakedWireframeMesh2d = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetMesh2dBindGroup<1>,
+    SetFaceBindGroup<2>,
+    // Draw the copy with the face data in its vertices
+    DrawFaceMesh,
+);
+
 /// Plugin that renders [`WireframeMesh2d`]s
 pub struct WireframeMesh2dPlugin;
 
+/// What [`queue_wireframe_mesh2d`] needs of each wireframe entity
+pub struct WireframeMesh2dInstance {
+    /// The mesh the wireframe is drawn from
+    pub mesh_asset_id: AssetId<Mesh>,
+    /// The depth the wireframe is sorted by
+    pub z: f32,
+}
+
 /// Our custom pipeline needs its own instance storage
+///
+/// Wireframe items are batched by bevy's own 2d mesh batching, which compares
+/// the entities' instances in
+/// [`RenderMesh2dInstances`](bevy::sprite::RenderMesh2dInstances). Every
+/// wireframe entity has one, as it has a [`Mesh2dHandle`].
 #[derive(Resource, Deref, DerefMut, Default)]
-pub struct WireframeMesh2dInstances(EntityHashMap<Entity, RenderMesh2dInstance>);
+pub struct WireframeMesh2dInstances(EntityHashMap<Entity, WireframeMesh2dInstance>);
 
 impl Plugin for WireframeMesh2dPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(crate::compute::FacePlugin);
+        if !app.is_plugin_added::<FacePlugin>() {
+            app.add_plugins(FacePlugin);
+        }
+        if !app.is_plugin_added::<ExtractComponentPlugin<WireframeView>>() {
+            app.add_plugins(ExtractComponentPlugin::<WireframeView>::default());
+        }
+        if !app.is_plugin_added::<ExtractComponentPlugin<WireframeScaleFactor>>() {
+            app.add_plugins(ExtractComponentPlugin::<WireframeScaleFactor>::default());
+        }
         embedded_asset!(app, "wireframe.wgsl");
+        app.init_resource::<WireframeSettings>().add_systems(
+            Last,
+            collect_wireframe_mesh2d.in_set(CollectWireframeMeshes),
+        );
 
         let render_app = app.sub_app_mut(RenderApp);
         // Register our custom draw function, and add our render systems
         render_app
             .add_render_command::<Transparent2d, DrawWireframeMesh2d>()
+            .add_render_command::<Transparent2d, DrawIndexedWireframeMesh2d>()
+            .add_render_command::<Transparent2d, DrawBakedWireframeMesh2d>()
             .init_resource::<SpecializedMeshPipelines<WireframeMesh2dPipeline>>()
             .init_resource::<WireframeMesh2dInstances>()
+            .init_resource::<WireframeStyles<WireframeMesh2d>>()
+            .init_resource::<WireframeBindGroups<WireframeMesh2d>>()
             .add_systems(
                 ExtractSchedule,
                 extract_wireframe_mesh2d.after(extract_mesh2d),
             )
             .add_systems(
                 Render,
-                (prepare_wireframe2d_bind_group.in_set(RenderSet::PrepareBindGroups),),
+                (
+                    prepare_wireframe_styles::<Transparent2d, WireframeMesh2d>,
+                    prepare_wireframe2d_bind_group,
+                )
+                    .chain()
+                    .in_set(RenderSet::PrepareBindGroups),
             )
             .add_systems(
                 Render,
@@ -135,49 +515,93 @@ impl Plugin for WireframeMesh2dPlugin {
     }
 
     fn finish(&self, app: &mut App) {
+        init_backend(app);
         // Register our custom pipeline
         app.sub_app_mut(RenderApp)
             .init_resource::<WireframeMesh2dPipeline>();
     }
 }
 
+/// Add the meshes of the entities [`extract_wireframe_mesh2d`] extracts to
+/// [`WireframeMeshes`], so their face data is built
+pub(crate) fn collect_wireframe_mesh2d(
+    settings: Res<WireframeSettings>,
+    query: Query<(&Mesh2dHandle, Has<WireframeMesh2d>), Without<NoWireframe>>,
+    mut wireframe_meshes: ResMut<WireframeMeshes>,
+) {
+    for (handle, marked) in &query {
+        if marked || settings.global {
+            wireframe_meshes.insert(&handle.0);
+        }
+    }
+}
+
 /// Extract the [`WireframeMesh2d`] marker component into the render app
+///
+/// With [`WireframeSettings::global`] set, every 2d mesh is extracted as if it
+/// were marked. The instances of entities that were despawned, hidden or
+/// unmarked are removed. Their bind groups are kept until a buffer they bind changes,
+/// and a mesh's face buffers are freed when the mesh asset is unused. With
+/// [`WireframeBackend::VertexAttributes`], entities are drawn from the baked
+/// copy of their mesh once it is made.
 #[allow(clippy::type_complexity)]
 pub fn extract_wireframe_mesh2d(
     mut commands: Commands,
     mut previous_len: Local<usize>,
     // When extracting, you must use `Extract` to mark the `SystemParam`s
     // which should be taken from the main world.
+    settings: Extract<Res<WireframeSettings>>,
+    baked_meshes: Extract<Res<BakedMeshes>>,
+    backend: Res<WireframeBackend>,
     query: Extract<
-        Query<(Entity, &ViewVisibility, &GlobalTransform, &Mesh2dHandle), With<WireframeMesh2d>>,
+        Query<
+            (
+                Entity,
+                &ViewVisibility,
+                &GlobalTransform,
+                &Mesh2dHandle,
+                Option<&WireframeStyle>,
+                Option<&RenderLayers>,
+                Has<WireframeMesh2d>,
+            ),
+            Without<NoWireframe>,
+        >,
     >,
     mut wireframe_mesh_instances: ResMut<WireframeMesh2dInstances>,
 ) {
     let mut values = Vec::with_capacity(*previous_len);
-    for (entity, view_visibility, transform, handle) in &query {
-        if !view_visibility.get() {
+    wireframe_mesh_instances.clear();
+    for (entity, view_visibility, transform, handle, style, layers, marked) in &query {
+        if !(marked || settings.global) || !view_visibility.get() {
             continue;
         }
-
-        let transforms = Mesh2dTransforms {
-            transform: (&transform.affine()).into(),
-            flags: MeshFlags::empty().bits(),
+        let Some(face_mesh) = baked_meshes.face_mesh(*backend, &handle.0) else {
+            continue;
+        };
+        let style = match style {
+            Some(style) => style.clone(),
+            None if marked => WireframeStyle::default(),
+            None => settings.global_style.clone(),
         };
 
-        values.push((entity, (handle.clone(), WireframeMesh2d)));
-
-        let mesh_asset_id = handle.0.id();
-        if !wireframe_mesh_instances.contains_key(&entity) {
-            wireframe_mesh_instances.insert(
-                entity,
-                RenderMesh2dInstance {
-                    mesh_asset_id,
-                    transforms,
-                    material_bind_group_id: Material2dBindGroupId::default(),
-                    automatic_batching: false,
-                },
-            );
-        }
+        values.push((
+            entity,
+            (
+                handle.clone(),
+                FaceMesh(face_mesh),
+                WireframeMesh2d,
+                style,
+                layers.cloned().unwrap_or_default(),
+            ),
+        ));
+
+        wireframe_mesh_instances.insert(
+            entity,
+            WireframeMesh2dInstance {
+                mesh_asset_id: face_mesh,
+                z: transform.translation().z,
+            },
+        );
     }
     *previous_len = values.len();
     commands.insert_or_spawn_batch(values);
@@ -192,21 +616,32 @@ pub fn queue_wireframe_mesh2d(
     pipeline_cache: Res<PipelineCache>,
     msaa: Res<Msaa>,
     render_meshes: Res<RenderAssets<GpuMesh>>,
+    backend: Res<WireframeBackend>,
+    pos_buffers: Res<PosBuffers>,
     wireframe_mesh_instances: Res<WireframeMesh2dInstances>,
+    mut skipped_meshes: Local<HashSet<AssetId<Mesh>>>,
+    styles: Query<(&WireframeStyle, &RenderLayers), With<WireframeMesh2d>>,
     mut views: Query<(
         &VisibleEntities,
         &mut SortedRenderPhase<Transparent2d>,
         &ExtractedView,
+        Option<&WireframeView>,
     )>,
 ) {
     if wireframe_mesh_instances.is_empty() {
         return;
     }
     // Iterate each view (a camera is a view)
-    for (visible_entities, mut transparent_phase, view) in &mut views {
+    for (visible_entities, mut transparent_phase, view, wireframe_view) in &mut views {
         let draw_wireframe_mesh2d = transparent_draw_functions
             .read()
             .id::<DrawWireframeMesh2d>();
+        let draw_indexed_wireframe_mesh2d = transparent_draw_functions
+            .read()
+            .id::<DrawIndexedWireframeMesh2d>();
+        let draw_baked_wireframe_mesh2d = transparent_draw_functions
+            .read()
+            .id::<DrawBakedWireframeMesh2d>();
 
         let mesh_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
             | Mesh2dPipelineKey::from_hdr(view.hdr);
@@ -215,79 +650,356 @@ pub fn queue_wireframe_mesh2d(
         for visible_entity in visible_entities.iter::<WithMesh2d>() {
             if let Some(mesh_instance) = wireframe_mesh_instances.get(visible_entity) {
                 let mesh2d_handle = mesh_instance.mesh_asset_id;
-                let mesh2d_transforms = &mesh_instance.transforms;
                 // Get our specialized pipeline
                 let mut mesh2d_key = mesh_key;
                 let Some(mesh) = render_meshes.get(mesh2d_handle) else {
-                    warn!("No mesh");
+                    // The mesh is still loading.
                     continue;
                 };
 
-                mesh2d_key |= Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology());
-                if !matches!(mesh.primitive_topology(), PrimitiveTopology::TriangleList) {
-                    panic!(
-                        "Expected a TriangleList but got {:?}",
-                        mesh.primitive_topology()
-                    );
+                let Some(topology) = wireframe_topology(mesh.primitive_topology()) else {
+                    if skipped_meshes.insert(mesh2d_handle) {
+                        warn!(
+                            "Skipping wireframe of {:?} mesh {:?}",
+                            mesh.primitive_topology(),
+                            mesh2d_handle
+                        );
+                    }
+                    continue;
+                };
+                mesh2d_key |= Mesh2dPipelineKey::from_primitive_topology(topology);
+                let Ok((style, layers)) = styles.get(*visible_entity) else {
+                    continue;
+                };
+                if wireframe_view.is_some_and(|wireframe_view| !wireframe_view.shows(layers)) {
+                    continue;
                 }
-                let pipeline_id = pipelines
-                    .specialize(
+                let lines = is_lines(mesh);
+                let Some(indexed) =
+                    face_data_indexed(*backend, &pos_buffers, mesh2d_handle, mesh, lines)
+                else {
+                    continue;
+                };
+                let draw_function = match (*backend, indexed || lines) {
+                    (WireframeBackend::VertexAttributes, _) => draw_baked_wireframe_mesh2d,
+                    (WireframeBackend::Compute, true) => draw_indexed_wireframe_mesh2d,
+                    (WireframeBackend::Compute, false) => draw_wireframe_mesh2d,
+                };
+                // Lines have no fill to hide edges behind.
+                let mode = if lines {
+                    WireframeMode::Shaded
+                } else {
+                    style.mode
+                };
+                // The passes share a sort key and are kept in the order they
+                // are added.
+                for pass in WireframePass::passes(mode) {
+                    let pipeline_id = match pipelines.specialize(
                         &pipeline_cache,
                         &wireframe_mesh2d_pipeline,
-                        mesh2d_key,
+                        WireframePipelineKey {
+                            mesh_key: mesh2d_key,
+                            space: style.space,
+                            indexed,
+                            lines,
+                            deformed: false,
+                            pass: *pass,
+                        },
                         &mesh.layout,
-                    )
-                    .expect("specialize 2d pipeline");
-
-                let mesh_z = mesh2d_transforms.transform.translation.z;
-                transparent_phase.add(Transparent2d {
-                    entity: *visible_entity,
-                    draw_function: draw_wireframe_mesh2d,
-                    pipeline: pipeline_id,
-                    // The 2d render items are sorted according to their z value before rendering,
-                    // in order to get correct transparency
-                    sort_key: FloatOrd(mesh_z),
-                    // This material is not batched
-                    batch_range: 0..1,
-                    extra_index: PhaseItemExtraIndex::NONE,
-                });
+                    ) {
+                        Ok(pipeline_id) => pipeline_id,
+                        Err(err) => {
+                            error!("{}", err);
+                            continue;
+                        }
+                    };
+
+                    let mesh_z = mesh_instance.z;
+                    transparent_phase.add(Transparent2d {
+                        entity: *visible_entity,
+                        draw_function,
+                        pipeline: pipeline_id,
+                        // The 2d render items are sorted according to their z value before rendering,
+                        // in order to get correct transparency
+                        sort_key: FloatOrd(mesh_z + style.depth_bias),
+                        // Batched with its neighbors that share its mesh and
+                        // pipeline once the phase is sorted
+                        batch_range: 0..1,
+                        extra_index: PhaseItemExtraIndex::NONE,
+                    });
+                }
             }
         }
     }
 }
 
-#[derive(Component)]
-pub struct Wireframe2dBindGroup(BindGroup);
+/// The face data and style bind group of a wireframe entity
+#[derive(Component, Clone)]
+pub struct WireframeBindGroup(pub(crate) BindGroup);
+
+/// The [`WireframeStyle`] of every wireframe instance drawn with the `M`
+/// marker
+///
+/// Styles are indexed like the mesh uniforms, so batched draws of entities
+/// that share a mesh and pipeline look up each instance's style in the shader.
+/// With [`WireframeBackend::VertexAttributes`], they are batched like the mesh
+/// uniforms in a uniform buffer too.
+#[derive(Resource)]
+pub struct WireframeStyles<M> {
+    pub(crate) buffer: StorageBuffer<Vec<WireframeStyleUniform>>,
+    pub(crate) uniform: Option<Buffer>,
+    marker: PhantomData<M>,
+}
+
+impl<M> Default for WireframeStyles<M> {
+    fn default() -> Self {
+        Self {
+            buffer: StorageBuffer::default(),
+            uniform: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// The face and style buffers a [`WireframeBindGroup`] was made with
+type BindGroupKey = (BufferId, BufferId);
+
+/// The [`WireframeBindGroup`]s of the wireframes drawn with the `M` marker,
+/// kept until a buffer they bind is made again
+#[derive(Resource)]
+pub struct WireframeBindGroups<M> {
+    /// The bind group shared by the entities using each mesh
+    meshes: HashMap<AssetId<Mesh>, (BindGroupKey, WireframeBindGroup)>,
+    /// The bind groups of deformed entities, which bind their own face data
+    deformed: EntityHashMap<Entity, (BindGroupKey, WireframeBindGroup)>,
+    /// The bind group of every wireframe with
+    /// [`WireframeBackend::VertexAttributes`], and the style buffer it binds
+    styles: Option<(BufferId, WireframeBindGroup)>,
+    marker: PhantomData<M>,
+}
+
+impl<M> Default for WireframeBindGroups<M> {
+    fn default() -> Self {
+        Self {
+            meshes: HashMap::default(),
+            deformed: EntityHashMap::default(),
+            styles: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> WireframeBindGroups<M> {
+    /// The bind group of the wireframes of mesh `id`, or of one deformed
+    /// entity, made again when its face or style buffer has been
+    pub(crate) fn get_or_create(
+        &mut self,
+        render_device: &RenderDevice,
+        layout: &BindGroupLayout,
+        id: AssetId<Mesh>,
+        pos_buffer: &PosBuffer,
+        deformed: Option<(Entity, &DeformedFaces)>,
+        styles: &WireframeStyles<M>,
+    ) -> Option<WireframeBindGroup> {
+        let face_buffer = match deformed {
+            Some((_, deformed)) => &deformed.face_buffer,
+            None => &pos_buffer.face_buffer,
+        };
+        let key = (face_buffer.id(), styles.buffer.buffer()?.id());
+        let cached = match deformed {
+            Some((entity, _)) => self.deformed.get(&entity),
+            None => self.meshes.get(&id),
+        };
+        if let Some((_, bind_group)) = cached.filter(|(cached_key, _)| *cached_key == key) {
+            return Some(bind_group.clone());
+        }
+        let bind_group = create_wireframe_bind_group(
+            render_device,
+            layout,
+            pos_buffer,
+            deformed.map(|(_, deformed)| deformed),
+            styles,
+        )?;
+        match deformed {
+            Some((entity, _)) => self.deformed.insert(entity, (key, bind_group.clone())),
+            None => self.meshes.insert(id, (key, bind_group.clone())),
+        };
+        Some(bind_group)
+    }
+
+    /// The bind group of every wireframe with
+    /// [`WireframeBackend::VertexAttributes`], made again when the style
+    /// buffer has been
+    pub(crate) fn styles(
+        &mut self,
+        render_device: &RenderDevice,
+        wireframe: &WireframeShader,
+        styles: &WireframeStyles<M>,
+    ) -> Option<WireframeBindGroup> {
+        let buffer = styles.uniform.as_ref()?.id();
+        if let Some((_, bind_group)) = self.styles.as_ref().filter(|(id, _)| *id == buffer) {
+            return Some(bind_group.clone());
+        }
+        let bind_group = create_style_bind_group(render_device, wireframe, styles)?;
+        self.styles = Some((buffer, bind_group.clone()));
+        Some(bind_group)
+    }
+
+    /// Drop the bind groups of the meshes without face data and of the
+    /// entities that are no longer deformed
+    pub(crate) fn retain(
+        &mut self,
+        pos_buffers: &PosBuffers,
+        mut is_deformed: impl FnMut(Entity) -> bool,
+    ) {
+        self.meshes.retain(|id, _| pos_buffers.get(*id).is_some());
+        self.deformed.retain(|entity, _| is_deformed(*entity));
+    }
+}
+
+/// Write the style of each wireframe item at its instance index
+///
+/// This runs after the phase items are batched, when their instance indices
+/// are known. Only the first item of a batch is drawn, with the instance range
+/// and dynamic offset of the whole batch, and the others may keep the range
+/// they were queued with, so the items are walked like the render loop does.
+/// The instance indices of mesh uniforms batched in a uniform buffer start
+/// again at each dynamic offset, where their styles start too.
+pub(crate) fn prepare_wireframe_styles<I: SortedPhaseItem, M: Component>(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    backend: Res<WireframeBackend>,
+    mut styles: ResMut<WireframeStyles<M>>,
+    views: Query<(&SortedRenderPhase<I>, Option<&WireframeScaleFactor>)>,
+    wireframes: Query<&WireframeStyle, With<M>>,
+) {
+    let style_size = WireframeStyleUniform::SHADER_SIZE.get();
+    let style_batch_size = match *backend {
+        WireframeBackend::Compute => 1,
+        WireframeBackend::VertexAttributes => WireframeBackend::style_batch_size(&render_device),
+    };
+    let mut uniforms = Vec::new();
+    for (phase, scale_factor) in &views {
+        let scale_factor = scale_factor.map_or(1.0, |scale_factor| scale_factor.0);
+        // The items drawn by the current batch's first item, its first
+        // instance index and the index of its first style.
+        let mut drawn: Option<(Range<usize>, usize, usize)> = None;
+        for (i, item) in phase.items.iter().enumerate() {
+            if !drawn.as_ref().is_some_and(|(items, ..)| items.contains(&i)) {
+                let instances = item.batch_range();
+                if instances.is_empty() {
+                    // Skipped by the render loop.
+                    drawn = None;
+                    continue;
+                }
+                let batch = item
+                    .extra_index()
+                    .as_dynamic_offset()
+                    .map_or(0, |offset| (offset.get() as u64 / style_size) as usize);
+                drawn = Some((i..i + instances.len(), instances.start as usize, batch));
+            }
+            let Some((items, first_instance, batch)) = &drawn else {
+                continue;
+            };
+            let Ok(style) = wireframes.get(item.entity()) else {
+                continue;
+            };
+            let index = batch + first_instance + (i - items.start);
+            // The whole batch is bound, so it must fit in the buffer.
+            let len = (index + 1).max(batch + style_batch_size as usize);
+            if uniforms.len() < len {
+                uniforms.resize(len, WireframeStyleUniform::default());
+            }
+            uniforms[index] = WireframeStyleUniform::scaled(style, scale_factor);
+        }
+    }
+    if uniforms.len() < style_batch_size as usize {
+        // The buffer must not be empty.
+        uniforms.resize(style_batch_size as usize, WireframeStyleUniform::default());
+    }
+    match *backend {
+        WireframeBackend::Compute => {
+            *styles.buffer.get_mut() = uniforms;
+            styles.buffer.write_buffer(&render_device, &render_queue);
+        }
+        WireframeBackend::VertexAttributes => {
+            // The styles are laid out the same in a uniform array as in
+            // storage, which can hold any number of them.
+            let mut contents = encase::StorageBuffer::new(Vec::new());
+            contents.write(&uniforms).unwrap();
+            let contents = contents.into_inner();
+            match &styles.uniform {
+                Some(buffer) if buffer.size() >= contents.len() as u64 => {
+                    render_queue.write_buffer(buffer, 0, &contents);
+                }
+                _ => {
+                    styles.uniform = Some(render_device.create_buffer_with_data(
+                        &BufferInitDescriptor {
+                            label: Some("wireframe_style_buffer"),
+                            contents: &contents,
+                            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+}
 
 pub fn prepare_wireframe2d_bind_group(
     mut commands: Commands,
     pipeline: Res<WireframeMesh2dPipeline>,
     render_device: Res<RenderDevice>,
-    query: Query<(Entity, &FaceBuffer)>,
+    styles: Res<WireframeStyles<WireframeMesh2d>>,
+    mut bind_groups: ResMut<WireframeBindGroups<WireframeMesh2d>>,
+    pos_buffers: Res<PosBuffers>,
+    query: Query<(Entity, &FaceMesh), With<WireframeMesh2d>>,
 ) {
-    for (entity, face_buffer) in query.iter() {
-        commands
-            .entity(entity)
-            .insert(Wireframe2dBindGroup(render_device.create_bind_group(
-                "wireframe2d_bind_group",
-                &pipeline.wireframe2d_layout,
-                &BindGroupEntries::single(face_buffer.as_entire_buffer_binding()),
-            )));
+    if pipeline.wireframe.backend == WireframeBackend::VertexAttributes {
+        let Some(bind_group) = bind_groups.styles(&render_device, &pipeline.wireframe, &styles)
+        else {
+            warn!("no style buffer");
+            return;
+        };
+        for (entity, _) in &query {
+            commands.entity(entity).insert(bind_group.clone());
+        }
+        return;
+    }
+    bind_groups.retain(&pos_buffers, |_| false);
+    // Entities that share a mesh share its bind group.
+    for (entity, face_mesh) in query.iter() {
+        let Some(pos_buffer) = pos_buffers.get(**face_mesh) else {
+            // The face data is uploaded once the mesh is.
+            continue;
+        };
+        let Some(bind_group) = bind_groups.get_or_create(
+            &render_device,
+            &pipeline.wireframe.layout,
+            **face_mesh,
+            pos_buffer,
+            None,
+            &styles,
+        ) else {
+            warn!("no style buffer");
+            continue;
+        };
+        commands.entity(entity).insert(bind_group);
     }
 }
 
 pub struct SetFaceBindGroup<const I: usize>;
 impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetFaceBindGroup<I> {
-    type Param = ();
+    type Param = SRes<WireframeBackend>;
     type ViewQuery = ();
-    type ItemQuery = Read<Wireframe2dBindGroup>;
+    type ItemQuery = Read<WireframeBindGroup>;
 
     #[inline]
     fn render<'w>(
         item: &P,
         _view: (),
-        bind_group: Option<&'w Wireframe2dBindGroup>,
-        _mesh2d_bind_group: (),
+        bind_group: Option<&'w WireframeBindGroup>,
+        backend: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         let mut dynamic_offsets: [u32; 1] = Default::default();
@@ -295,6 +1007,9 @@ impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetFaceBindGroup<I> {
         if let Some(dynamic_offset) = item.extra_index().as_dynamic_offset() {
             dynamic_offsets[offset_count] = dynamic_offset.get();
             offset_count += 1;
+        } else if *backend.into_inner() == WireframeBackend::VertexAttributes {
+            // The mesh uniforms are in storage, and the styles in one batch.
+            offset_count += 1;
         }
         let Some(bind_group) = bind_group else {
             warn!("no bind group");
@@ -304,3 +1019,81 @@ impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetFaceBindGroup<I> {
         RenderCommandResult::Success
     }
 }
+
+/// Draws the triangles of an indexed mesh as a plain triangle list, or the
+/// segments of a line mesh as quads
+///
+/// The vertex shader pulls each vertex through the mesh's indices, which lets
+/// it know which triangle the vertex belongs to, and widens each segment into
+/// a quad around it.
+pub struct DrawMeshFaces;
+impl<P: PhaseItem> RenderCommand<P> for DrawMeshFaces {
+    type Param = SRes<PosBuffers>;
+    type ViewQuery = ();
+    type ItemQuery = Read<FaceMesh>;
+
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        face_mesh: Option<&'w FaceMesh>,
+        pos_buffers: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(face_mesh) = face_mesh else {
+            warn!("no face mesh");
+            return RenderCommandResult::Failure;
+        };
+        let Some(pos_buffer) = pos_buffers.into_inner().get(**face_mesh) else {
+            // The face data is uploaded once the mesh is.
+            return RenderCommandResult::Failure;
+        };
+        pass.draw(
+            0..pos_buffer.pulled_vertex_count(),
+            item.batch_range().clone(),
+        );
+        RenderCommandResult::Success
+    }
+}
+
+/// Draws the [`GpuMesh`] of the item's [`FaceMesh`], which is the baked copy
+/// of its mesh with [`WireframeBackend::VertexAttributes`]
+pub struct DrawFaceMesh;
+impl<P: PhaseItem> RenderCommand<P> for DrawFaceMesh {
+    type Param = SRes<RenderAssets<GpuMesh>>;
+    type ViewQuery = ();
+    type ItemQuery = Read<FaceMesh>;
+
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        face_mesh: Option<&'w FaceMesh>,
+        meshes: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(face_mesh) = face_mesh else {
+            warn!("no face mesh");
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(**face_mesh) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        let batch_range = item.batch_range();
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, batch_range.clone());
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, batch_range.clone());
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
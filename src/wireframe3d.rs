@@ -0,0 +1,450 @@
+use crate::{
+    backend::{init_backend, BakedMeshes, WireframeBackend},
+    compute::*,
+    deform::{prepare_deformed_faces, DeformPlugin, DeformedFaceBuffers, WireframeDeformation},
+    style::{
+        NoWireframe, WireframeMode, WireframeScaleFactor, WireframeSettings, WireframeStyle,
+        WireframeView,
+    },
+    wireframe2d::{
+        face_data_indexed, is_lines, prepare_wireframe_styles, wireframe_descriptor,
+        wireframe_topology, DrawFaceMesh, DrawMeshFaces, SetFaceBindGroup, WireframeBindGroups,
+        WireframePass, WireframePipelineKey, WireframeShader, WireframeStyles,
+    },
+};
+use bevy::{
+    app::{App, Last, Plugin},
+    asset::{embedded_asset, AssetId, Handle},
+    core_pipeline::core_3d::Transparent3d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With, Without},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+        world::{FromWorld, World},
+    },
+    log::{error, warn},
+    pbr::{
+        DrawMesh, MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup,
+        SetMeshViewBindGroup,
+    },
+    render::{
+        extract_component::ExtractComponentPlugin,
+        mesh::{GpuMesh, Mesh, MeshVertexBufferLayoutRef},
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItemExtraIndex, SetItemPipeline,
+            SortedRenderPhase,
+        },
+        render_resource::{
+            Face, PipelineCache, RenderPipelineDescriptor, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines,
+        },
+        renderer::RenderDevice,
+        view::{ExtractedView, Msaa, RenderLayers, ViewVisibility, VisibleEntities, WithMesh},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    utils::HashSet,
+};
+
+/// Marks a 3d mesh to be drawn as a wireframe
+#[derive(Component, Default, Clone)]
+pub struct WireframeMesh3d;
+
+#[derive(Resource)]
+pub struct WireframeMesh3dPipeline {
+    /// this pipeline wraps the standard [`MeshPipeline`]
+    mesh_pipeline: MeshPipeline,
+    wireframe: WireframeShader,
+}
+
+impl FromWorld for WireframeMesh3dPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh_pipeline: MeshPipeline::from_world(world),
+            wireframe: WireframeShader::from_world(world),
+        }
+    }
+}
+
+// We implement `SpecializedPipeline` to customize the default rendering from `MeshPipeline`
+impl SpecializedMeshPipeline for WireframeMesh3dPipeline {
+    type Key = WireframePipelineKey<MeshPipelineKey>;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key.mesh_key, layout)?;
+        wireframe_descriptor(
+            &mut descriptor,
+            &self.wireframe,
+            &key,
+            layout,
+            vec!["WIREFRAME_3D".into()],
+        )?;
+        // Only the front faces of the fill hide edges behind them. 2d meshes
+        // keep both faces, so flipped ones are still drawn.
+        if key.pass != WireframePass::Shaded {
+            descriptor.primitive.cull_mode = Some(Face::Back);
+        }
+        descriptor.label = Some("wireframe_mesh3d_pipeline".into());
+        Ok(descriptor)
+    }
+}
+
+// This specifies how to render a wireframe 3d mesh
+type DrawWireframeMesh3d = (
+    // Set the pipeline
+    SetItemPipeline,
+    // Set the view uniform as bind group 0
+    SetMeshViewBindGroup<0>,
+    // Set the mesh uniform as bind group 1
+    SetMeshBindGroup<1>,
+    // Set the face buffer as bind group 2
+    SetFaceBindGroup<2>,
+    // Draw the mesh
+    DrawMesh,
+);
+
+// This specifies how to render an indexed 3d mesh
+type DrawIndexedWireframeMesh3d = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetFaceBindGroup<2>,
+    // Draw the mesh's triangles without its index buffer
+    DrawMeshFaces,
+);
+
+// This specifies how to render a 3d mesh from its baked copy
+type DrawBakedWireframeMesh3d = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetFaceBindGroup<2>,
+    // Draw the copy with the face data in its vertices
+    DrawFaceMesh,
+);
+
+/// Plugin that renders [`WireframeMesh3d`]s
+///
+/// Wireframes are queued into [`Transparent3d`]. A [`WireframeStyle`] with an
+/// opaque fill color is drawn without blending and writes depth. Skinned and
+/// morphed meshes are drawn in their current pose by the [`DeformPlugin`],
+/// unless the [`WireframeBackend`] has no compute shaders.
+pub struct WireframeMesh3dPlugin;
+
+impl Plugin for WireframeMesh3dPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FacePlugin>() {
+            app.add_plugins(FacePlugin);
+        }
+        if !app.is_plugin_added::<DeformPlugin>() {
+            app.add_plugins(DeformPlugin);
+        }
+        if !app.is_plugin_added::<ExtractComponentPlugin<WireframeView>>() {
+            app.add_plugins(ExtractComponentPlugin::<WireframeView>::default());
+        }
+        if !app.is_plugin_added::<ExtractComponentPlugin<WireframeScaleFactor>>() {
+            app.add_plugins(ExtractComponentPlugin::<WireframeScaleFactor>::default());
+        }
+        embedded_asset!(app, "wireframe.wgsl");
+        app.init_resource::<WireframeSettings>().add_systems(
+            Last,
+            collect_wireframe_mesh3d.in_set(CollectWireframeMeshes),
+        );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        // Register our custom draw function, and add our render systems
+        render_app
+            .add_render_command::<Transparent3d, DrawWireframeMesh3d>()
+            .add_render_command::<Transparent3d, DrawIndexedWireframeMesh3d>()
+            .add_render_command::<Transparent3d, DrawBakedWireframeMesh3d>()
+            .init_resource::<SpecializedMeshPipelines<WireframeMesh3dPipeline>>()
+            .init_resource::<WireframeStyles<WireframeMesh3d>>()
+            .init_resource::<WireframeBindGroups<WireframeMesh3d>>()
+            .add_systems(ExtractSchedule, extract_wireframe_mesh3d)
+            .add_systems(
+                Render,
+                (
+                    prepare_wireframe_styles::<Transparent3d, WireframeMesh3d>,
+                    prepare_wireframe3d_bind_group.after(prepare_deformed_faces),
+                )
+                    .chain()
+                    .in_set(RenderSet::PrepareBindGroups),
+            )
+            .add_systems(
+                Render,
+                queue_wireframe_mesh3d.in_set(RenderSet::QueueMeshes),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        init_backend(app);
+        // Register our custom pipeline
+        app.sub_app_mut(RenderApp)
+            .init_resource::<WireframeMesh3dPipeline>();
+    }
+}
+
+/// Add the meshes of the entities [`extract_wireframe_mesh3d`] extracts to
+/// [`WireframeMeshes`], so their face data is built
+pub(crate) fn collect_wireframe_mesh3d(
+    settings: Res<WireframeSettings>,
+    query: Query<(&Handle<Mesh>, Has<WireframeMesh3d>), Without<NoWireframe>>,
+    mut wireframe_meshes: ResMut<WireframeMeshes>,
+) {
+    for (handle, marked) in &query {
+        if marked || settings.global {
+            wireframe_meshes.insert(handle);
+        }
+    }
+}
+
+/// Extract the [`WireframeMesh3d`] marker component into the render app
+///
+/// The mesh instance itself is extracted by bevy's own mesh extraction. With
+/// [`WireframeSettings::global`] set, every 3d mesh is extracted as if it were
+/// marked.
+#[allow(clippy::type_complexity)]
+pub fn extract_wireframe_mesh3d(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    settings: Extract<Res<WireframeSettings>>,
+    baked_meshes: Extract<Res<BakedMeshes>>,
+    backend: Res<WireframeBackend>,
+    query: Extract<
+        Query<
+            (
+                Entity,
+                &ViewVisibility,
+                &Handle<Mesh>,
+                Option<&WireframeStyle>,
+                Option<&RenderLayers>,
+                Has<WireframeMesh3d>,
+            ),
+            Without<NoWireframe>,
+        >,
+    >,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, view_visibility, handle, style, layers, marked) in &query {
+        if !(marked || settings.global) || !view_visibility.get() {
+            continue;
+        }
+        let Some(face_mesh) = baked_meshes.face_mesh(*backend, handle) else {
+            continue;
+        };
+        let style = match style {
+            Some(style) => style.clone(),
+            None if marked => WireframeStyle::default(),
+            None => settings.global_style.clone(),
+        };
+        values.push((
+            entity,
+            (
+                FaceMesh(face_mesh),
+                WireframeMesh3d,
+                style,
+                layers.cloned().unwrap_or_default(),
+            ),
+        ));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+/// Queue the 3d meshes marked with [`WireframeMesh3d`] using our custom pipeline and draw function
+#[allow(clippy::too_many_arguments)]
+pub fn queue_wireframe_mesh3d(
+    transparent_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    wireframe_mesh3d_pipeline: Res<WireframeMesh3dPipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<WireframeMesh3dPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_meshes: Res<RenderAssets<GpuMesh>>,
+    backend: Res<WireframeBackend>,
+    pos_buffers: Res<PosBuffers>,
+    render_mesh_instances: Res<RenderMeshInstances>,
+    mut skipped_meshes: Local<HashSet<AssetId<Mesh>>>,
+    wireframes: Query<
+        (
+            &FaceMesh,
+            &WireframeStyle,
+            &RenderLayers,
+            Has<WireframeDeformation>,
+        ),
+        With<WireframeMesh3d>,
+    >,
+    mut views: Query<(
+        &VisibleEntities,
+        &mut SortedRenderPhase<Transparent3d>,
+        &ExtractedView,
+        Option<&WireframeView>,
+    )>,
+) {
+    if wireframes.is_empty() {
+        return;
+    }
+    // Iterate each view (a camera is a view)
+    for (visible_entities, mut transparent_phase, view, wireframe_view) in &mut views {
+        let draw_wireframe_mesh3d = transparent_draw_functions
+            .read()
+            .id::<DrawWireframeMesh3d>();
+        let draw_indexed_wireframe_mesh3d = transparent_draw_functions
+            .read()
+            .id::<DrawIndexedWireframeMesh3d>();
+        let draw_baked_wireframe_mesh3d = transparent_draw_functions
+            .read()
+            .id::<DrawBakedWireframeMesh3d>();
+        let rangefinder = view.rangefinder3d();
+
+        let view_key = MeshPipelineKey::from_msaa_samples(msaa.samples())
+            | MeshPipelineKey::from_hdr(view.hdr);
+
+        // Queue all entities visible to that view
+        for visible_entity in visible_entities.iter::<WithMesh>() {
+            let Ok((face_mesh, style, layers, deformed)) = wireframes.get(*visible_entity) else {
+                continue;
+            };
+            if wireframe_view.is_some_and(|wireframe_view| !wireframe_view.shows(layers)) {
+                continue;
+            }
+            let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(*visible_entity)
+            else {
+                continue;
+            };
+            // With the vertex attribute backend, this is the baked copy of the
+            // entity's mesh.
+            let Some(mesh) = render_meshes.get(**face_mesh) else {
+                // The mesh is still loading.
+                continue;
+            };
+            let Some(topology) = wireframe_topology(mesh.primitive_topology()) else {
+                if skipped_meshes.insert(**face_mesh) {
+                    warn!(
+                        "Skipping wireframe of {:?} mesh {:?}",
+                        mesh.primitive_topology(),
+                        **face_mesh
+                    );
+                }
+                continue;
+            };
+            let lines = is_lines(mesh);
+            let Some(indexed) = face_data_indexed(*backend, &pos_buffers, **face_mesh, mesh, lines)
+            else {
+                continue;
+            };
+            let draw_function = match (*backend, indexed || lines) {
+                (WireframeBackend::VertexAttributes, _) => draw_baked_wireframe_mesh3d,
+                (WireframeBackend::Compute, true) => draw_indexed_wireframe_mesh3d,
+                (WireframeBackend::Compute, false) => draw_wireframe_mesh3d,
+            };
+
+            // Get our specialized pipeline
+            let mut mesh_key = view_key | MeshPipelineKey::from_primitive_topology(topology);
+            // Lines fade out at the sides of their quads.
+            if lines || style.fill_color.to_linear().alpha < 1.0 {
+                mesh_key |= MeshPipelineKey::BLEND_ALPHA;
+            }
+            // Lines have no fill to hide edges behind.
+            let mode = if lines {
+                WireframeMode::Shaded
+            } else {
+                style.mode
+            };
+            let distance =
+                rangefinder.distance_translation(&mesh_instance.translation) + style.depth_bias;
+            for pass in WireframePass::passes(mode) {
+                let pipeline_id = match pipelines.specialize(
+                    &pipeline_cache,
+                    &wireframe_mesh3d_pipeline,
+                    WireframePipelineKey {
+                        mesh_key,
+                        space: style.space,
+                        indexed,
+                        lines,
+                        deformed,
+                        pass: *pass,
+                    },
+                    &mesh.layout,
+                ) {
+                    Ok(pipeline_id) => pipeline_id,
+                    Err(err) => {
+                        error!("{}", err);
+                        continue;
+                    }
+                };
+
+                transparent_phase.add(Transparent3d {
+                    entity: *visible_entity,
+                    draw_function,
+                    pipeline: pipeline_id,
+                    // The depth of every hidden line fill is written before
+                    // any edges are drawn, so edges are hidden by the fills
+                    // of other entities whatever order they are sorted in.
+                    distance: match pass {
+                        WireframePass::Fill => f32::NEG_INFINITY,
+                        _ => distance,
+                    },
+                    // Batched with its neighbors that share its mesh and
+                    // pipeline once the phase is sorted
+                    batch_range: 0..1,
+                    extra_index: PhaseItemExtraIndex::NONE,
+                });
+            }
+        }
+    }
+}
+
+pub fn prepare_wireframe3d_bind_group(
+    mut commands: Commands,
+    pipeline: Res<WireframeMesh3dPipeline>,
+    render_device: Res<RenderDevice>,
+    styles: Res<WireframeStyles<WireframeMesh3d>>,
+    mut bind_groups: ResMut<WireframeBindGroups<WireframeMesh3d>>,
+    pos_buffers: Res<PosBuffers>,
+    deformed_buffers: Res<DeformedFaceBuffers>,
+    query: Query<(Entity, &FaceMesh), With<WireframeMesh3d>>,
+) {
+    if pipeline.wireframe.backend == WireframeBackend::VertexAttributes {
+        let Some(bind_group) = bind_groups.styles(&render_device, &pipeline.wireframe, &styles)
+        else {
+            warn!("no style buffer");
+            return;
+        };
+        for (entity, _) in &query {
+            commands.entity(entity).insert(bind_group.clone());
+        }
+        return;
+    }
+    bind_groups.retain(&pos_buffers, |entity| {
+        deformed_buffers.get(entity).is_some()
+    });
+    // Entities that share a mesh share its bind group, and deformed entities
+    // have their own positions and face data.
+    for (entity, face_mesh) in query.iter() {
+        let Some(pos_buffer) = pos_buffers.get(**face_mesh) else {
+            // The face data is uploaded once the mesh is.
+            continue;
+        };
+        let deformed = deformed_buffers
+            .get(entity)
+            .map(|deformed| (entity, deformed));
+        let Some(bind_group) = bind_groups.get_or_create(
+            &render_device,
+            &pipeline.wireframe.layout,
+            **face_mesh,
+            pos_buffer,
+            deformed,
+            &styles,
+        ) else {
+            warn!("no style buffer");
+            continue;
+        };
+        commands.entity(entity).insert(bind_group);
+    }
+}
@@ -0,0 +1,200 @@
+#import bevy_pbr::{
+    mesh_functions,
+    skinning,
+    morph::morph,
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    view_transformations::position_world_to_clip,
+}
+
+struct WireframeExtension {
+    wire_color: vec4<f32>,
+    line_width: f32,
+    sharpness: f32,
+};
+@group(2) @binding(100) var<uniform> wireframe: WireframeExtension;
+
+// The mesh's vertex and the barycentric coordinates added in `specialize()`
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+    @location(0) position: vec3<f32>,
+#ifdef VERTEX_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef VERTEX_UVS_A
+    @location(2) uv: vec2<f32>,
+#endif
+#ifdef VERTEX_UVS_B
+    @location(3) uv_b: vec2<f32>,
+#endif
+#ifdef VERTEX_TANGENTS
+    @location(4) tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(5) color: vec4<f32>,
+#endif
+#ifdef SKINNED
+    @location(6) joint_indices: vec4<u32>,
+    @location(7) joint_weights: vec4<f32>,
+#endif
+    @location(8) bary: vec3<f32>,
+};
+
+// bevy_pbr's `VertexOutput` with the barycentric coordinates
+struct WireframeVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+#ifdef VERTEX_UVS_A
+    @location(2) uv: vec2<f32>,
+#endif
+#ifdef VERTEX_UVS_B
+    @location(3) uv_b: vec2<f32>,
+#endif
+#ifdef VERTEX_TANGENTS
+    @location(4) world_tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(5) color: vec4<f32>,
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    @location(6) @interpolate(flat) instance_index: u32,
+#endif
+    @location(8) bary: vec3<f32>,
+    // The morphed position before skinning, which model space widths are
+    // measured in
+    @location(9) local_position: vec3<f32>,
+};
+
+#ifdef MORPH_TARGETS
+fn morph_vertex(vertex_in: Vertex) -> Vertex {
+    var vertex = vertex_in;
+    let weight_count = bevy_pbr::morph::layer_count();
+    for (var i: u32 = 0u; i < weight_count; i ++) {
+        let weight = bevy_pbr::morph::weight_at(i);
+        if weight == 0.0 {
+            continue;
+        }
+        vertex.position += weight * morph(vertex.index, bevy_pbr::morph::position_offset, i);
+#ifdef VERTEX_NORMALS
+        vertex.normal += weight * morph(vertex.index, bevy_pbr::morph::normal_offset, i);
+#endif
+#ifdef VERTEX_TANGENTS
+        vertex.tangent += vec4(weight * morph(vertex.index, bevy_pbr::morph::tangent_offset, i), 0.0);
+#endif
+    }
+    return vertex;
+}
+#endif
+
+/// Entry point for the vertex shader, bevy_pbr's mesh vertex shader passing
+/// the barycentric coordinates through
+@vertex
+fn vertex(vertex_no_morph: Vertex) -> WireframeVertexOutput {
+    var out: WireframeVertexOutput;
+#ifdef MORPH_TARGETS
+    var vertex = morph_vertex(vertex_no_morph);
+#else
+    var vertex = vertex_no_morph;
+#endif
+#ifdef SKINNED
+    let model = skinning::skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+    let model = mesh_functions::get_model_matrix(vertex_no_morph.instance_index);
+#endif
+#ifdef VERTEX_NORMALS
+#ifdef SKINNED
+    out.world_normal = skinning::skin_normals(model, vertex.normal);
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex_no_morph.instance_index);
+#endif
+#endif
+    out.world_position = mesh_functions::mesh_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_UVS_B
+    out.uv_b = vertex.uv_b;
+#endif
+#ifdef VERTEX_TANGENTS
+    out.world_tangent = mesh_functions::mesh_tangent_local_to_world(model, vertex.tangent, vertex_no_morph.instance_index);
+#endif
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex_no_morph.instance_index;
+#endif
+    out.local_position = vertex.position;
+    out.bary = vertex.bary;
+    return out;
+}
+
+/// The distance from a fragment to each edge of its triangle, in pixels or
+/// model units
+fn edge_distances(in: WireframeVertexOutput) -> vec3<f32> {
+#ifdef SCREEN_SPACE
+    return in.bary / fwidth(in.bary);
+#else
+    // The gradient of each coordinate along the triangle, solved from its
+    // change and the position's change between pixels.
+    let dx = dpdx(in.local_position);
+    let dy = dpdy(in.local_position);
+    let db_dx = dpdx(in.bary);
+    let db_dy = dpdy(in.bary);
+    let xx = dot(dx, dx);
+    let xy = dot(dx, dy);
+    let yy = dot(dy, dy);
+    let det = xx * yy - xy * xy;
+    let grad2 = (yy * db_dx * db_dx - 2.0 * xy * db_dx * db_dy + xx * db_dy * db_dy) / det;
+    return in.bary / sqrt(grad2);
+#endif
+}
+
+/// Entry point for the fragment shader, bevy_pbr's forward fragment shader
+/// with the wire mixed over its lit output
+@fragment
+fn fragment(
+    in: WireframeVertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var mesh: VertexOutput;
+    mesh.position = in.position;
+    mesh.world_position = in.world_position;
+    mesh.world_normal = in.world_normal;
+#ifdef VERTEX_UVS_A
+    mesh.uv = in.uv;
+#endif
+#ifdef VERTEX_UVS_B
+    mesh.uv_b = in.uv_b;
+#endif
+#ifdef VERTEX_TANGENTS
+    mesh.world_tangent = in.world_tangent;
+#endif
+#ifdef VERTEX_COLORS
+    mesh.color = in.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    mesh.instance_index = in.instance_index;
+#endif
+
+    var pbr_input = pbr_input_from_standard_material(mesh, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+
+    // The wire is unlit, but fogged and tonemapped with the surface.
+    let dist = edge_distances(in);
+    let d = min(dist.x, min(dist.y, dist.z)) / wireframe.line_width;
+    let I = exp2(-wireframe.sharpness * d * d) * wireframe.wire_color.a;
+    out.color = vec4<f32>(mix(out.color.rgb, wireframe.wire_color.rgb, I), out.color.a);
+
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
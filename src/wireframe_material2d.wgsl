@@ -0,0 +1,60 @@
+// Import the standard 2d mesh uniforms and set their bind groups
+#import bevy_sprite::mesh2d_functions
+
+struct WireframeMaterial2d {
+    wire_color: vec4<f32>,
+    fill_color: vec4<f32>,
+    line_width: f32,
+    sharpness: f32,
+};
+@group(2) @binding(0) var<uniform> material: WireframeMaterial2d;
+
+// The structure of the vertex buffer is as specified in `specialize()`
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) bary: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // The model position, to measure the barycentric coordinates in model units
+    @location(0) position: vec2<f32>,
+    @location(1) bary: vec3<f32>,
+};
+
+/// Entry point for the vertex shader
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let model = mesh2d_functions::get_model_matrix(vertex.instance_index);
+    out.clip_position = mesh2d_functions::mesh2d_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+    out.position = vertex.position.xy;
+    out.bary = vertex.bary;
+    return out;
+}
+
+/// Entry point for the fragment shader
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The distance to each edge is its barycentric coordinate over how fast
+    // that coordinate changes. Hidden edges have a constant coordinate and are
+    // infinitely far away.
+#ifdef SCREEN_SPACE
+    let dist = in.bary / fwidth(in.bary);
+#else
+    // Solve for the gradient of the coordinates in model units from their
+    // change and the model position's change between pixels.
+    let dx = dpdx(in.position);
+    let dy = dpdy(in.position);
+    let db_dx = dpdx(in.bary);
+    let db_dy = dpdy(in.bary);
+    let det = dx.x * dy.y - dx.y * dy.x;
+    let grad_x = (dy.y * db_dx - dx.y * db_dy) / det;
+    let grad_y = (dx.x * db_dy - dy.x * db_dx) / det;
+    let dist = in.bary / sqrt(grad_x * grad_x + grad_y * grad_y);
+#endif
+    let d = min(dist.x, min(dist.y, dist.z)) / material.line_width;
+    let I = exp2(-material.sharpness * d * d);
+    return I * material.wire_color + (1.0 - I) * material.fill_color;
+}